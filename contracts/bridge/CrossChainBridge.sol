@@ -118,7 +118,12 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
         CREDENTIAL_VERIFICATION,
         CREDENTIAL_STATUS_UPDATE,
         ROLE_SYNCHRONIZATION,
-        DID_RESOLUTION
+        DID_RESOLUTION,
+        // Responses posted back by the Solana identity program
+        TOKEN_TRANSFER_RESPONSE,
+        CREDENTIAL_VERIFICATION_RESPONSE,
+        ROLE_SYNC_RESPONSE,
+        DID_RESOLUTION_RESPONSE
     }
 
     struct Message {
@@ -204,6 +209,11 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
         string targetChain
     );
     event TokenTransferCompleted(uint256 indexed transferId, bool success);
+    event TokenTransferDustRefunded(
+        uint256 indexed transferId,
+        address indexed sender,
+        uint256 dust
+    );
 
     // New events
     event CredentialVerificationRequested(
@@ -225,6 +235,11 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
         string targetChain
     );
     event RoleSynchronizationCompleted(uint256 indexed requestId, bool success);
+    event DIDResolutionCompleted(
+        uint256 indexed requestId,
+        bool resolved,
+        bytes didDocument
+    );
     event MessageReplayed(bytes32 messageId);
     event RateLimitExceeded(address sender, uint256 cooldownRemaining);
 
@@ -327,10 +342,12 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
             timestamp: block.timestamp
         });
         
-        // Include SBT token ID in the cross-chain message
+        // Include SBT token ID and the credential the SBT was issued on in
+        // the cross-chain message; the target chain verifies against it
+        bytes32 credentialHash = soulbound.getCredentialHash(tokenId);
         bytes memory payload = abi.encode(
             MessageType.VERIFICATION,
-            abi.encode(requestId, did, tokenId),
+            abi.encode(requestId, did, tokenId, credentialHash),
             block.timestamp,
             keccak256(abi.encodePacked(requestId, did, tokenId, block.timestamp))
         );
@@ -552,14 +569,21 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
         sendCrossChainMessage(targetChain, data);
     }
 
+    /**
+     * @dev Lock tokens and send them to `recipient` on `targetChain`. For
+     * Solana the recipient is the wallet that owns the receiving token account
+     */
     function bridgeTokens(
         address tokenAddress,
         uint256 amount,
-        string memory targetChain
+        string memory targetChain,
+        bytes32 recipient
     ) external {
         // Rate limiting
         _checkRateLimit(msg.sender);
 
+        require(recipient != bytes32(0), "Invalid recipient");
+
         require(
             bytes(bridgeEndpoints[targetChain]).length > 0,
             "Target chain not supported"
@@ -583,13 +607,14 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
 
         bytes memory payload = abi.encode(
             MessageType.TOKEN_TRANSFER,
-            abi.encode(transferId, tokenAddress, amount),
+            abi.encode(transferId, tokenAddress, amount, recipient),
             block.timestamp,
             keccak256(
                 abi.encodePacked(
                     transferId,
                     tokenAddress,
                     amount,
+                    recipient,
                     block.timestamp
                 )
             )
@@ -615,13 +640,29 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
         require(request.transferId == transferId, "Invalid transfer ID");
         require(!request.completed, "Transfer already completed");
 
+        _completeTokenTransfer(request, success, 0);
+    }
+
+    function _completeTokenTransfer(
+        TokenTransferRequest storage request,
+        bool success,
+        uint256 dust
+    ) internal {
+        uint256 transferId = request.transferId;
         request.completed = true;
         if (success) {
+            require(dust <= request.amount, "Invalid dust");
             // Burn tokens on Polygon (assumes Solana minting succeeded)
             CommodityToken(request.tokenAddress).burn(
                 address(this),
-                request.amount
+                request.amount - dust
             );
+            // Refund the part too small for the Solana mint's decimals,
+            // which was not released there
+            if (dust > 0) {
+                IERC20(request.tokenAddress).transfer(request.sender, dust);
+                emit TokenTransferDustRefunded(transferId, request.sender, dust);
+            }
         } else {
             // Refund tokens if bridging failed
             IERC20(request.tokenAddress).transfer(
@@ -715,8 +756,16 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
             // Emit completion event
             emit RoleSynchronizationCompleted(requestId, true);
         } else if (msgType == MessageType.TOKEN_TRANSFER) {
-            (uint256 transferId, address tokenAddress, uint256 amount) = abi
-                .decode(data, (uint256, address, uint256));
+            (
+                uint256 transferId,
+                address tokenAddress,
+                uint256 amount,
+                bytes32 recipient
+            ) = abi.decode(data, (uint256, address, uint256, bytes32));
+            require(
+                uint256(recipient) >> 160 == 0 && recipient != bytes32(0),
+                "Invalid recipient"
+            );
 
             // Process token transfer
             address token = tokenAddress;
@@ -728,10 +777,51 @@ function _checkSbtCredentials(address soulboundContract, uint256 tokenId) intern
                 keccak256(bytes("80002"))
             ) {
                 // Polygon Amoy
-                CommodityToken(token).mint(msg.sender, amount);
+                CommodityToken(token).mint(
+                    address(uint160(uint256(recipient))),
+                    amount
+                );
             }
 
             emit TokenTransferCompleted(transferId, true);
+        } else if (msgType == MessageType.TOKEN_TRANSFER_RESPONSE) {
+            (uint256 transferId, bool success, uint256 dust) = abi.decode(
+                data,
+                (uint256, bool, uint256)
+            );
+
+            TokenTransferRequest storage request = tokenTransferRequests[
+                transferId
+            ];
+            if (request.sender != address(0) && !request.completed) {
+                _completeTokenTransfer(request, success, dust);
+            }
+        } else if (msgType == MessageType.CREDENTIAL_VERIFICATION_RESPONSE) {
+            (uint256 requestId, bool verified) = abi.decode(
+                data,
+                (uint256, bool)
+            );
+
+            CredentialVerificationRequest
+                storage request = credentialVerificationRequests[requestId];
+            if (request.requestId == requestId && !request.verified) {
+                request.verified = verified;
+                emit CredentialVerificationCompleted(requestId, verified);
+            }
+        } else if (msgType == MessageType.ROLE_SYNC_RESPONSE) {
+            (uint256 requestId, bool success) = abi.decode(
+                data,
+                (uint256, bool)
+            );
+
+            if (roleSyncRequests[requestId].requestId == requestId) {
+                emit RoleSynchronizationCompleted(requestId, success);
+            }
+        } else if (msgType == MessageType.DID_RESOLUTION_RESPONSE) {
+            (uint256 requestId, bool resolved, bytes memory didDocument) = abi
+                .decode(data, (uint256, bool, bytes));
+
+            emit DIDResolutionCompleted(requestId, resolved, didDocument);
         }
     }
 }
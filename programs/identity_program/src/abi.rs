@@ -0,0 +1,224 @@
+//! Solidity ABI codec for payloads exchanged with the EVM contracts.
+//!
+//! `CrossChainBridge.sol` builds every message with `abi.encode`, so values
+//! are laid out as 32-byte big-endian words. Static types occupy one word in
+//! the head of the tuple; dynamic `bytes`/`string` values are stored in the
+//! tail as a length word followed by the data right-padded to a word boundary,
+//! and the head holds the byte offset of that tail entry.
+
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Size in bytes of a single ABI word.
+pub const WORD_SIZE: usize = 32;
+
+/// Cursor over the values of a top-level `abi.encode` tuple.
+///
+/// Values are read in declaration order. Every read is bounds-checked, and
+/// [`AbiReader::finish`] verifies that the input is exactly the canonical
+/// encoding of what was read: dynamic values laid out back to back right
+/// after the head, zero padding, and no trailing bytes.
+pub struct AbiReader<'a> {
+    data: &'a [u8],
+    head: usize,
+    // (offset, encoded length) of each dynamic value, in head order
+    tails: Vec<(usize, usize)>,
+}
+
+impl<'a> AbiReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: 0,
+            tails: Vec::new(),
+        }
+    }
+
+    /// Reads the next head word as-is.
+    pub fn read_word(&mut self) -> Result<[u8; 32]> {
+        let word = word_at(self.data, self.head)?;
+        self.head += WORD_SIZE;
+        Ok(word)
+    }
+
+    /// Decodes a `uint8` (or Solidity enum) value.
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(uint_from_word(&self.read_word()?, 1)? as u8)
+    }
+
+    /// Decodes a `uint256` value, failing if it does not fit in a `u64`.
+    pub fn read_u64(&mut self) -> Result<u64> {
+        Ok(uint_from_word(&self.read_word()?, 8)? as u64)
+    }
+
+    /// Decodes a `uint256` value, failing if it does not fit in a `u128`.
+    pub fn read_u128(&mut self) -> Result<u128> {
+        uint_from_word(&self.read_word()?, 16)
+    }
+
+    /// Decodes a `bool`, rejecting any word other than 0 or 1.
+    pub fn read_bool(&mut self) -> Result<bool> {
+        let word = self.read_word()?;
+        require!(
+            word[..WORD_SIZE - 1].iter().all(|b| *b == 0) && word[WORD_SIZE - 1] <= 1,
+            ErrorCode::MalformedPayload
+        );
+        Ok(word[WORD_SIZE - 1] == 1)
+    }
+
+    /// Decodes a 20-byte EVM `address`, which must be left-padded with zeros.
+    pub fn read_address(&mut self) -> Result<[u8; 20]> {
+        let word = self.read_word()?;
+        require!(
+            word[..12].iter().all(|b| *b == 0),
+            ErrorCode::MalformedPayload
+        );
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&word[12..]);
+        Ok(address)
+    }
+
+    /// Decodes a `bytes32` value.
+    pub fn read_bytes32(&mut self) -> Result<[u8; 32]> {
+        self.read_word()
+    }
+
+    /// Decodes a dynamic `bytes` or `string` value. Strings are returned as
+    /// their raw UTF-8 bytes.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let offset = to_usize(uint_from_word(&self.read_word()?, 8)?)?;
+        let len = to_usize(uint_from_word(&word_at(self.data, offset)?, 8)?)?;
+        let start = offset + WORD_SIZE;
+        let value = slice_at(self.data, start, len)?;
+
+        let padded_len = len.div_ceil(WORD_SIZE) * WORD_SIZE;
+        let padding = slice_at(self.data, start + len, padded_len - len)?;
+        require!(
+            padding.iter().all(|b| *b == 0),
+            ErrorCode::MalformedPayload
+        );
+
+        self.tails.push((offset, WORD_SIZE + padded_len));
+        Ok(value)
+    }
+
+    /// Checks that every byte of the input has been accounted for by the
+    /// values read so far, in canonical order.
+    pub fn finish(self) -> Result<()> {
+        let mut end = self.head;
+        for (offset, len) in self.tails {
+            require!(offset == end, ErrorCode::MalformedPayload);
+            end += len;
+        }
+        require!(end == self.data.len(), ErrorCode::MalformedPayload);
+        Ok(())
+    }
+}
+
+enum Slot {
+    Static([u8; 32]),
+    Dynamic(Vec<u8>),
+}
+
+/// Builds an `abi.encode` tuple one value at a time.
+#[derive(Default)]
+pub struct AbiEncoder {
+    slots: Vec<Slot>,
+}
+
+impl AbiEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an unsigned integer as a `uint256` (or `uint8`/enum) word.
+    pub fn push_uint(mut self, value: u128) -> Self {
+        self.slots.push(Slot::Static(uint_word(value)));
+        self
+    }
+
+    pub fn push_bool(self, value: bool) -> Self {
+        self.push_uint(value as u128)
+    }
+
+    pub fn push_address(mut self, address: &[u8; 20]) -> Self {
+        self.slots.push(Slot::Static(address_word(address)));
+        self
+    }
+
+    pub fn push_bytes32(mut self, value: &[u8; 32]) -> Self {
+        self.slots.push(Slot::Static(*value));
+        self
+    }
+
+    /// Appends a dynamic `bytes` value; strings are encoded the same way.
+    pub fn push_bytes(mut self, value: &[u8]) -> Self {
+        self.slots.push(Slot::Dynamic(value.to_vec()));
+        self
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let head_len = self.slots.len() * WORD_SIZE;
+        let mut head = Vec::with_capacity(head_len);
+        let mut tail = Vec::new();
+        for slot in self.slots {
+            match slot {
+                Slot::Static(word) => head.extend_from_slice(&word),
+                Slot::Dynamic(data) => {
+                    head.extend_from_slice(&uint_word((head_len + tail.len()) as u128));
+                    tail.extend_from_slice(&uint_word(data.len() as u128));
+                    let padded_len = data.len().div_ceil(WORD_SIZE) * WORD_SIZE;
+                    tail.extend_from_slice(&data);
+                    tail.resize(tail.len() + padded_len - data.len(), 0);
+                }
+            }
+        }
+        head.extend_from_slice(&tail);
+        head
+    }
+}
+
+/// Big-endian `uint256` word holding `value`.
+pub fn uint_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Left-padded word holding a 20-byte EVM address, which is also the
+/// Wormhole universal address format for EVM chains.
+pub fn address_word(address: &[u8; 20]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+fn word_at(data: &[u8], start: usize) -> Result<[u8; 32]> {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(slice_at(data, start, WORD_SIZE)?);
+    Ok(word)
+}
+
+fn slice_at(data: &[u8], start: usize, len: usize) -> Result<&[u8]> {
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or_else(|| error!(ErrorCode::PayloadTooShort))
+}
+
+fn to_usize(value: u128) -> Result<usize> {
+    usize::try_from(value).map_err(|_| error!(ErrorCode::PayloadTooShort))
+}
+
+// Reads the low `width` bytes of a big-endian word, requiring the rest to be zero.
+fn uint_from_word(word: &[u8; 32], width: usize) -> Result<u128> {
+    let split = WORD_SIZE - width;
+    require!(
+        word[..split].iter().all(|b| *b == 0),
+        ErrorCode::AbiValueOutOfRange
+    );
+    Ok(word[split..]
+        .iter()
+        .fold(0u128, |acc, b| (acc << 8) | *b as u128))
+}
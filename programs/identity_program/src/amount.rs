@@ -0,0 +1,95 @@
+//! Conversion of token amounts between EVM token contracts and SPL mints.
+//!
+//! Transfer payloads carry a `uint256` amount in the decimals of the token
+//! contract, 18 for `CommodityToken`, while SPL mints hold a `u64` in their
+//! own decimals, usually fewer. Amounts are kept as 32-byte big-endian words
+//! until they have been scaled, so large wei amounts are never truncated.
+//! Inbound tokens are already burned or locked on the source chain, so a
+//! remainder below the smallest SPL unit is split off and returned for the
+//! transfer record rather than failing the transfer. Outbound amounts must
+//! convert exactly, and fail with `TransferAmountHasDust` otherwise. A result
+//! too large for the target type fails with `TransferAmountOverflow`.
+
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Converts a `uint256` amount in `foreign_decimals` to an SPL amount in
+/// `decimals`. Returns the amount and the dust truncated from it, as a
+/// `uint256` in `foreign_decimals`.
+pub fn to_spl_amount(amount: &[u8; 32], foreign_decimals: u8, decimals: u8) -> Result<(u64, [u8; 32])> {
+    let mut value = *amount;
+    for _ in decimals..foreign_decimals {
+        div_by_ten(&mut value);
+    }
+    let mut truncated = value;
+    for _ in decimals..foreign_decimals {
+        mul_by_ten(&mut truncated);
+    }
+    let dust = sub(amount, &truncated);
+    require!(
+        value[..24].iter().all(|b| *b == 0),
+        ErrorCode::TransferAmountOverflow
+    );
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&value[24..]);
+    let mut spl_amount = u64::from_be_bytes(low);
+    for _ in foreign_decimals..decimals {
+        spl_amount = spl_amount
+            .checked_mul(10)
+            .ok_or_else(|| error!(ErrorCode::TransferAmountOverflow))?;
+    }
+    Ok((spl_amount, dust))
+}
+
+/// Converts an SPL amount in `decimals` to a `uint256` amount in
+/// `foreign_decimals`.
+pub fn to_foreign_amount(amount: u64, decimals: u8, foreign_decimals: u8) -> Result<[u8; 32]> {
+    let mut spl_amount = amount;
+    for _ in foreign_decimals..decimals {
+        let remainder = spl_amount % 10;
+        require!(remainder == 0, ErrorCode::TransferAmountHasDust);
+        spl_amount /= 10;
+    }
+    let mut value = [0u8; 32];
+    value[24..].copy_from_slice(&spl_amount.to_be_bytes());
+    for _ in decimals..foreign_decimals {
+        require!(mul_by_ten(&mut value) == 0, ErrorCode::TransferAmountOverflow);
+    }
+    Ok(value)
+}
+
+// Divides a big-endian word by ten in place, returning the remainder
+fn div_by_ten(value: &mut [u8; 32]) -> u16 {
+    let mut remainder = 0u16;
+    for byte in value.iter_mut() {
+        let current = (remainder << 8) | *byte as u16;
+        *byte = (current / 10) as u8;
+        remainder = current % 10;
+    }
+    remainder
+}
+
+// Subtracts big-endian words, `b` being no larger than `a`
+fn sub(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut difference = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let current = a[i] as i16 - b[i] as i16 - borrow;
+        borrow = (current < 0) as i16;
+        difference[i] = current.rem_euclid(256) as u8;
+    }
+    difference
+}
+
+// Multiplies a big-endian word by ten in place, returning the carry out of
+// the top byte
+fn mul_by_ten(value: &mut [u8; 32]) -> u16 {
+    let mut carry = 0u16;
+    for byte in value.iter_mut().rev() {
+        let current = *byte as u16 * 10 + carry;
+        *byte = current as u8;
+        carry = current >> 8;
+    }
+    carry
+}
@@ -0,0 +1,253 @@
+//! `did:sol` identifiers and W3C DID Core rendering of on-chain DID records.
+//!
+//! The DID strings carried by `VerificationEvent.did` and
+//! `DIDResolutionEvent.did` use the form `did:sol:<network>:<pubkey>`, where
+//! `<network>` is one of `mainnet`, `devnet`, `testnet` or `localnet` and
+//! `<pubkey>` is the base58 encoding of a 32-byte Solana public key.
+//!
+//! [`render_document`] turns a [`DidDocument`] into a DID Core JSON document
+//! and [`to_canonical_string`] serializes it with sorted keys and no
+//! insignificant whitespace, so that off-chain verifiers can compare
+//! documents byte for byte. This module is only built with the
+//! `did-document` feature and is not used by the on-chain program.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_lang::prelude::*;
+use serde_json::{json, Map, Value};
+
+use crate::{DidDocument, DidRecord, ErrorCode, VerificationKeyType, VerificationMethod};
+
+pub const DID_CORE_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+pub const MULTIKEY_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+
+// Multicodec prefixes for public keys in `publicKeyMultibase`
+const ED25519_PUB_CODEC: [u8; 2] = [0xed, 0x01];
+const SECP256K1_PUB_CODEC: [u8; 2] = [0xe7, 0x01];
+
+/// Solana cluster named by a `did:sol` identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolanaNetwork {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl SolanaNetwork {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SolanaNetwork::Mainnet => "mainnet",
+            SolanaNetwork::Devnet => "devnet",
+            SolanaNetwork::Testnet => "testnet",
+            SolanaNetwork::Localnet => "localnet",
+        }
+    }
+}
+
+impl FromStr for SolanaNetwork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mainnet" => Ok(SolanaNetwork::Mainnet),
+            "devnet" => Ok(SolanaNetwork::Devnet),
+            "testnet" => Ok(SolanaNetwork::Testnet),
+            "localnet" => Ok(SolanaNetwork::Localnet),
+            _ => Err(ErrorCode::InvalidDid.into()),
+        }
+    }
+}
+
+/// A parsed `did:sol:<network>:<pubkey>` identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolDid {
+    pub network: SolanaNetwork,
+    pub account: Pubkey,
+}
+
+impl SolDid {
+    pub fn new(network: SolanaNetwork, account: Pubkey) -> Self {
+        Self { network, account }
+    }
+}
+
+impl FromStr for SolDid {
+    type Err = Error;
+
+    /// Parses the canonical form only: lowercase method and network names,
+    /// and a base58 key that re-encodes to the same string.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let (Some("did"), Some("sol"), Some(network), Some(account), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ErrorCode::InvalidDid.into());
+        };
+
+        let network = network.parse()?;
+        let account = Pubkey::from_str(account).map_err(|_| error!(ErrorCode::InvalidDid))?;
+        let did = Self { network, account };
+        require!(did.to_string() == s, ErrorCode::InvalidDid);
+        Ok(did)
+    }
+}
+
+impl fmt::Display for SolDid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "did:sol:{}:{}", self.network.as_str(), self.account)
+    }
+}
+
+/// Renders a DID document as DID Core JSON. Controllers are Solana keys and
+/// are written as `did:sol` identifiers on `network`. Every verification
+/// method is listed under `authentication` and `assertionMethod`.
+pub fn render_document(document: &DidDocument, network: SolanaNetwork) -> Value {
+    let did = &document.did;
+    let method_ids: Vec<Value> = document
+        .verification_methods
+        .iter()
+        .map(|method| Value::String(format!("{did}#{}", method.id)))
+        .collect();
+
+    let mut rendered = Map::new();
+    rendered.insert("@context".into(), json!([DID_CORE_CONTEXT, MULTIKEY_CONTEXT]));
+    rendered.insert("id".into(), json!(did));
+    rendered.insert(
+        "controller".into(),
+        document
+            .controllers
+            .iter()
+            .map(|controller| Value::String(SolDid::new(network, *controller).to_string()))
+            .collect(),
+    );
+    if !method_ids.is_empty() {
+        rendered.insert(
+            "verificationMethod".into(),
+            document
+                .verification_methods
+                .iter()
+                .map(|method| render_verification_method(did, method))
+                .collect(),
+        );
+        rendered.insert("authentication".into(), Value::Array(method_ids.clone()));
+        rendered.insert("assertionMethod".into(), Value::Array(method_ids));
+    }
+    if !document.services.is_empty() {
+        rendered.insert(
+            "service".into(),
+            document
+                .services
+                .iter()
+                .map(|service| {
+                    json!({
+                        "id": format!("{did}#{}", service.id),
+                        "type": service.service_type,
+                        "serviceEndpoint": service.endpoint,
+                    })
+                })
+                .collect(),
+        );
+    }
+    Value::Object(rendered)
+}
+
+/// DID resolution metadata for a record: creation and update times as
+/// `xsd:dateTime`, the deactivation flag and the off-chain document CID.
+pub fn render_metadata(record: &DidRecord) -> Value {
+    let mut metadata = Map::new();
+    metadata.insert("created".into(), json!(format_timestamp(record.created_at)));
+    metadata.insert("updated".into(), json!(format_timestamp(record.updated_at)));
+    metadata.insert("deactivated".into(), json!(record.deactivated));
+    if let Some(cid) = &record.document.document_cid {
+        metadata.insert("documentCid".into(), json!(cid));
+    }
+    Value::Object(metadata)
+}
+
+/// Canonical serialization: object keys in lexicographic order, no
+/// whitespace between tokens.
+pub fn to_canonical_string(value: &Value) -> String {
+    sort_keys(value).to_string()
+}
+
+// Copy of `value` with every object's entries inserted in key order. Sorting
+// here rather than relying on `Map` keeps the output stable if serde_json's
+// `preserve_order` feature is enabled anywhere in the dependency graph
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(sort_keys).collect()),
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            Value::Object(entries.into_iter().map(|(key, value)| (key.clone(), sort_keys(value))).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
+/// `publicKeyMultibase` value for a verification key: base58btc (`z`) of the
+/// multicodec-prefixed key. Uncompressed secp256k1 keys are compressed first.
+pub fn public_key_multibase(method: &VerificationMethod) -> Result<String> {
+    let mut bytes = Vec::with_capacity(2 + 33);
+    match (method.key_type, method.public_key.len()) {
+        (VerificationKeyType::Ed25519, 32) => {
+            bytes.extend_from_slice(&ED25519_PUB_CODEC);
+            bytes.extend_from_slice(&method.public_key);
+        }
+        (VerificationKeyType::Secp256k1, 33) => {
+            bytes.extend_from_slice(&SECP256K1_PUB_CODEC);
+            bytes.extend_from_slice(&method.public_key);
+        }
+        (VerificationKeyType::Secp256k1, 65) if method.public_key[0] == 0x04 => {
+            bytes.extend_from_slice(&SECP256K1_PUB_CODEC);
+            bytes.push(0x02 | (method.public_key[64] & 1));
+            bytes.extend_from_slice(&method.public_key[1..33]);
+        }
+        _ => return Err(ErrorCode::InvalidVerificationMethod.into()),
+    }
+    Ok(format!("z{}", bs58::encode(bytes).into_string()))
+}
+
+fn render_verification_method(did: &str, method: &VerificationMethod) -> Value {
+    let mut rendered = json!({
+        "id": format!("{did}#{}", method.id),
+        "type": "Multikey",
+        "controller": did,
+    });
+    // Key lengths and SEC1 prefixes are checked by `DidDocument::validate`
+    // when stored, so this only fails for accounts written outside the
+    // program; such a method is rendered without a key
+    if let Ok(key) = public_key_multibase(method) {
+        rendered["publicKeyMultibase"] = json!(key);
+    }
+    rendered
+}
+
+// Unix seconds as `YYYY-MM-DDThh:mm:ssZ`
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Gregorian date for a count of days since 1970-01-01 (H. Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
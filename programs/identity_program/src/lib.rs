@@ -1,5 +1,23 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::secp256k1_recover::secp256k1_recover;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use wormhole_anchor_sdk::wormhole;
+
+pub mod abi;
+pub mod amount;
+pub mod payload;
+#[cfg(feature = "did-document")]
+pub mod did;
+
+use abi::{AbiEncoder, AbiReader};
+use amount::{to_foreign_amount, to_spl_amount};
+use payload::*;
 
 declare_id!("HU18d3qUrvLK52mQ2AoNKEnV6m1B6VreZ8M7eUE5GBew");
 
@@ -9,47 +27,217 @@ pub mod wormhole_constants {
    
     // Wormhole program ID for Solana devnet
     pub const WORMHOLE_PROGRAM_ID: Pubkey = solana_program::pubkey!("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+
+    // Seed prefix for the accounts holding our outbound Wormhole messages
+    pub const SEED_PREFIX_SENT: &[u8] = b"sent";
+}
+
+// Roles shared with the EVM contracts; each is keccak256 of its name, as in
+// MultiChainAccess.sol and SoulboundNFT.sol
+pub mod roles {
+    pub const DEFAULT_ADMIN_ROLE: [u8; 32] = [0u8; 32];
+
+    // keccak256("VERIFIER_ROLE")
+    pub const VERIFIER_ROLE: [u8; 32] = [
+        0x0c, 0xe2, 0x3c, 0x3e, 0x39, 0x98, 0x18, 0xcf,
+        0xee, 0x81, 0xa7, 0xab, 0x08, 0x80, 0xf7, 0x14,
+        0xe5, 0x3d, 0x76, 0x72, 0xb0, 0x8d, 0xf0, 0xfa,
+        0x62, 0xf2, 0x84, 0x34, 0x16, 0xe1, 0xea, 0x09,
+    ];
+
+    // keccak256("BRIDGE_ROLE")
+    pub const BRIDGE_ROLE: [u8; 32] = [
+        0x52, 0xba, 0x82, 0x4b, 0xfa, 0xbc, 0x2b, 0xcf,
+        0xcd, 0xf7, 0xf0, 0xed, 0xbb, 0x48, 0x6e, 0xbb,
+        0x05, 0xe1, 0x83, 0x6c, 0x90, 0xe7, 0x80, 0x47,
+        0xef, 0xeb, 0x94, 0x99, 0x90, 0xf7, 0x2e, 0x5f,
+    ];
+
+    // keccak256("CREDENTIAL_MANAGER_ROLE")
+    pub const CREDENTIAL_MANAGER_ROLE: [u8; 32] = [
+        0x65, 0x9d, 0x1c, 0xaa, 0x2e, 0xcc, 0x59, 0xfb,
+        0x39, 0x8a, 0xc5, 0x9d, 0x0f, 0x2b, 0x9f, 0xda,
+        0x07, 0x05, 0x09, 0x8c, 0x7f, 0xf7, 0xd2, 0xe7,
+        0x69, 0xf4, 0x58, 0x97, 0x2b, 0x1e, 0x82, 0xbd,
+    ];
+
+    // keccak256("BRIDGE_ADMIN_ROLE"), from CrossChainBridge.sol
+    pub const BRIDGE_ADMIN_ROLE: [u8; 32] = [
+        0x75, 0x1b, 0x79, 0x5d, 0x24, 0xb9, 0x2e, 0x3d,
+        0x92, 0xd1, 0xd0, 0xd8, 0xf2, 0x88, 0x5f, 0x4e,
+        0x9c, 0x9c, 0x26, 0x9d, 0xa3, 0x50, 0xaf, 0x36,
+        0xae, 0x6b, 0x49, 0x06, 0x9b, 0xab, 0xf4, 0xbf,
+    ];
 }
 
 #[program]
 pub mod identity_program {
     use super::*;
 
+    // Create the program state and give the authority DEFAULT_ADMIN_ROLE, the
+    // root of the role hierarchy, like the MultiChainAccess constructor
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
         let state = &mut ctx.accounts.state;
-        state.authority = ctx.accounts.authority.key();
+        state.authority = authority;
         state.verification_count = 0;
         state.credential_count = 0;
+
+        let role_assignment = &mut ctx.accounts.role_assignment;
+        role_assignment.role = roles::DEFAULT_ADMIN_ROLE;
+        role_assignment.account = authority;
+        role_assignment.source_chain = wormhole::CHAIN_ID_SOLANA;
+        role_assignment.granted_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(RoleUpdatedEvent {
+            request_id: 0,
+            role: roles::DEFAULT_ADMIN_ROLE,
+            account: authority,
+            is_grant: true,
+            sender: authority,
+        });
+
         Ok(())
     }
 
-    pub fn receive_message(ctx: Context<ReceiveMessage>, vaa: Vec<u8>) -> Result<()> {
-        // Manual parsing of the VAA - simplified version
-       
-        // Extract payload from VAA - this is a placeholder
-        let payload_bytes = &vaa[..];
-        let payload: MessagePayload = deserialize(payload_bytes)?;
-        
+    // Set up the program's Wormhole emitter and record the core bridge accounts
+    // it publishes through. An initial message is posted so that the emitter's
+    // sequence tracker exists before the first response goes out.
+    pub fn initialize_wormhole(ctx: Context<InitializeWormhole>) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::DEFAULT_ADMIN_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+
+        let config = &mut ctx.accounts.wormhole_config;
+        config.bridge = ctx.accounts.wormhole_bridge.key();
+        config.fee_collector = ctx.accounts.wormhole_fee_collector.key();
+        config.sequence = ctx.accounts.wormhole_sequence.key();
+        config.nonce = 0;
+
+        ctx.accounts.wormhole_emitter.bump = ctx.bumps.wormhole_emitter;
+
+        post_message(
+            ctx.accounts.wormhole_program.to_account_info(),
+            wormhole::PostMessage {
+                config: ctx.accounts.wormhole_bridge.to_account_info(),
+                message: ctx.accounts.wormhole_message.to_account_info(),
+                emitter: ctx.accounts.wormhole_emitter.to_account_info(),
+                sequence: ctx.accounts.wormhole_sequence.to_account_info(),
+                payer: ctx.accounts.authority.to_account_info(),
+                fee_collector: ctx.accounts.wormhole_fee_collector.to_account_info(),
+                clock: ctx.accounts.clock.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            ctx.accounts.wormhole_bridge.fee(),
+            ctx.bumps.wormhole_emitter,
+            ctx.bumps.wormhole_message,
+            0,
+            response_payload(
+                MessageType::Custom,
+                0,
+                0,
+                current_sequence(&ctx.accounts.wormhole_sequence),
+                Vec::new(),
+            )?,
+        )
+    }
+
+    // Sequence number the core bridge will assign to our next outbound message
+    pub fn next_sequence(ctx: Context<NextSequence>) -> Result<u64> {
+        Ok(ctx.accounts.wormhole_sequence.value())
+    }
+
+    pub fn receive_message(ctx: Context<ReceiveMessage>, vaa_hash: [u8; 32]) -> Result<()> {
+        // The posted VAA account is created by the core bridge only after the
+        // guardian signatures have been verified, so its payload can be trusted
+        // once we know the account is the canonical one for this hash.
+        let posted_vaa = &ctx.accounts.posted_vaa;
+        require!(
+            posted_vaa.payload_size() as usize == posted_vaa.data().len(),
+            ErrorCode::InvalidVaaPayload
+        );
+        let payload = MessagePayload::decode(posted_vaa.data().as_ref())?;
+        let source_chain = posted_vaa.emitter_chain();
+        // Each message gets at most one response, posted under this sequence
+        let response_sequence = ctx.accounts.wormhole.wormhole_sequence.value();
+
+        // A VAA can only be processed once: the receipt is created with
+        // `init`, so a resubmission fails the whole transaction
+        let received = &mut ctx.accounts.received;
+        received.emitter_chain = source_chain;
+        received.sequence = posted_vaa.sequence();
+        received.vaa_hash = vaa_hash;
+        received.message_id = payload.message_id;
+        emit!(MessageReceivedEvent {
+            emitter_chain: source_chain,
+            sequence: received.sequence,
+            message_id: payload.message_id,
+            vaa_hash,
+        });
+
         let state = &mut ctx.accounts.state;
 
         match payload.msg_type {
             MessageType::Verification => {
-                let (request_id, did) = deserialize_verification(&payload.data)?;
+                let (request_id, did, sbt_token_id, credential_hash) =
+                    deserialize_verification(&payload.data)?;
+
+                // A DID verifies if it is registered and active here and holds
+                // the named credential: active, from an issuer trusted for its
+                // type, and issued to the DID account or one of its
+                // controllers. Its primary controller then holds the soulbound
+                // identity token
+                let did_record = match load_did_record(ctx.accounts.did_record.as_ref(), &did)? {
+                    Some(did_record) => {
+                        let did_account = ctx.accounts.did_record.as_ref().map(|account| account.key());
+                        trusted_credential(
+                            ctx.accounts.credential.as_ref(),
+                            ctx.accounts.trusted_issuer.as_ref(),
+                            &credential_hash,
+                        )?
+                        .filter(|credential| {
+                            Some(credential.subject) == did_account || did_record.is_controlled_by(&credential.subject)
+                        })
+                        .map(|_| did_record)
+                    }
+                    None => None,
+                };
+                let verified = did_record.is_some();
+                if let Some(did_record) = &did_record {
+                    ctx.accounts.issue_identity_token(did_record, sbt_token_id, credential_hash, source_chain)?;
+                }
+                ctx.accounts.record_verification(VerificationRecord {
+                    source_chain,
+                    request_id,
+                    did: VecToString::try_into(did.clone())?,
+                    subject: did_record.map(|did_record| did_record.document.controllers[0]),
+                    sbt_token_id,
+                    verified,
+                    requested_at: payload.timestamp,
+                    processed_at: Clock::get()?.unix_timestamp as u64,
+                    response_sequence,
+                })?;
+
                 emit!(VerificationEvent {
                     request_id,
                     did: VecToString::try_into(did)?,
-                    verified: true,
+                    verified,
                 });
-                state.verification_count += 1;
-
-                // Store response for later use
-                let _response_payload = serialize(&MessagePayload {
-                    msg_type: MessageType::VerificationResponse,
-                    data: serialize(&VerificationResponse { request_id, verified: true })?,
-                    timestamp: Clock::get()?.unix_timestamp as u64,
-                    message_id: solana_program::hash::hash(&serialize(&request_id)?).to_bytes(),
-                })?;
-                // Placeholder for future implementation
+                ctx.accounts.state.verification_count += 1;
+
+                ctx.accounts.post_response(
+                    response_payload(
+                        MessageType::VerificationResponse,
+                        request_id,
+                        source_chain,
+                        response_sequence,
+                        VerificationResponse { request_id, verified }.encode(),
+                    )?,
+                    &ctx.bumps,
+                )?;
             }
             MessageType::AssetCreation => {
                 let (issuer, name, symbol) = deserialize_asset_creation(&payload.data)?;
@@ -60,108 +248,176 @@ pub mod identity_program {
                 });
             }
             MessageType::TokenTransfer => {
-                let (transfer_id, _token_address, amount) = deserialize_token_transfer(&payload.data)?;
-                let mint_ctx = ctx.accounts.into_mint_context();
-                anchor_spl::token::mint_to(mint_ctx, amount as u64)?;
-
-                // Store response for later use
-                let _response_payload = serialize(&MessagePayload {
-                    msg_type: MessageType::TokenTransferResponse,
-                    data: serialize(&TokenTransferResponse { transfer_id, success: true })?,
-                    timestamp: Clock::get()?.unix_timestamp as u64,
-                    message_id: solana_program::hash::hash(&serialize(&transfer_id)?).to_bytes(),
+                let (transfer_id, token_address, foreign_amount, recipient) =
+                    deserialize_token_transfer(&payload.data)?;
+                let missing = || error!(ErrorCode::MissingTokenAccount);
+                let recipient_account = ctx.accounts.recipient.as_ref().ok_or_else(missing)?;
+                require_keys_eq!(recipient_account.owner, recipient, ErrorCode::InvalidRecipient);
+                let mint = ctx.accounts.token_mint.as_ref().ok_or_else(missing)?.key();
+                let asset =
+                    registered_asset(ctx.accounts.wrapped_asset.as_deref(), source_chain, &token_address, &mint)?;
+                let (amount, dust) = to_spl_amount(&foreign_amount, asset.foreign_decimals, asset.decimals)?;
+                ctx.accounts.record_transfer(TransferRecord {
+                    transfer_id,
+                    source_chain,
+                    mint,
+                    token_address,
+                    amount,
+                    dust,
+                    account: recipient,
+                    created_at: Clock::get()?.unix_timestamp as u64,
                 })?;
-                // Placeholder for future implementation
+                ctx.accounts.release_tokens(amount, ctx.bumps.token_authority)?;
+
+                emit!(TokenTransferReceivedEvent {
+                    transfer_id,
+                    source_chain,
+                    token_address,
+                    mint,
+                    amount,
+                    dust,
+                    recipient,
+                });
+
+                ctx.accounts.post_response(
+                    response_payload(
+                        MessageType::TokenTransferResponse,
+                        transfer_id,
+                        source_chain,
+                        response_sequence,
+                        TokenTransferResponse {
+                            transfer_id,
+                            success: true,
+                            dust,
+                        }
+                        .encode(),
+                    )?,
+                    &ctx.bumps,
+                )?;
             }
             MessageType::CredentialVerification => {
                 let (request_id, credential_hash) = deserialize_credential_verification(&payload.data)?;
-                
-                // For now, just emit an event - in a real implementation, we would check the credential
+                let verified = trusted_credential(
+                    ctx.accounts.credential.as_ref(),
+                    ctx.accounts.trusted_issuer.as_ref(),
+                    &credential_hash,
+                )?
+                .is_some();
+
                 emit!(CredentialVerificationEvent {
                     request_id,
                     credential_hash,
-                    verified: true,
+                    verified,
                 });
                 
                 state.credential_count += 1;
                 
-                // Store response for later use
-                let _response_payload = serialize(&MessagePayload {
-                    msg_type: MessageType::CredentialVerificationResponse,
-                    data: serialize(&CredentialVerificationResponse { 
-                        request_id, 
-                        verified: true 
-                    })?,
-                    timestamp: Clock::get()?.unix_timestamp as u64,
-                    message_id: solana_program::hash::hash(&serialize(&request_id)?).to_bytes(),
-                })?;
+                ctx.accounts.post_response(
+                    response_payload(
+                        MessageType::CredentialVerificationResponse,
+                        request_id,
+                        source_chain,
+                        response_sequence,
+                        CredentialVerificationResponse { request_id, verified }.encode(),
+                    )?,
+                    &ctx.bumps,
+                )?;
             }
             MessageType::RoleSynchronization => {
-                let (request_id, role, account, is_grant) = deserialize_role_sync(&payload.data)?;
-                
-                // In a real implementation, we would update our role registry
-                // For now, just emit an event
+                let (request_id, role, foreign_address, is_grant) = deserialize_role_sync(&payload.data)?;
+                let account = linked_account(ctx.accounts.address_link.as_deref(), source_chain, &foreign_address)?;
+                ctx.accounts.apply_role_sync(role, account, is_grant, source_chain)?;
+
                 emit!(RoleSyncEvent {
                     request_id,
                     role,
                     account,
                     is_grant,
+                    source_chain,
+                    foreign_address,
                 });
                 
-                // Store response for later use
-                let _response_payload = serialize(&MessagePayload {
-                    msg_type: MessageType::RoleSyncResponse,
-                    data: serialize(&RoleSyncResponse { 
-                        request_id, 
-                        success: true 
-                    })?,
-                    timestamp: Clock::get()?.unix_timestamp as u64,
-                    message_id: solana_program::hash::hash(&serialize(&request_id)?).to_bytes(),
-                })?;
+                ctx.accounts.post_response(
+                    response_payload(
+                        MessageType::RoleSyncResponse,
+                        request_id,
+                        source_chain,
+                        response_sequence,
+                        RoleSyncResponse { request_id, success: true }.encode(),
+                    )?,
+                    &ctx.bumps,
+                )?;
             }
             MessageType::DIDResolution => {
-                let (request_id, did) = deserialize_verification(&payload.data)?;
-                
-                // For now, just emit an event - in a real implementation, we would resolve the DID
+                let (request_id, did) = deserialize_did_resolution(&payload.data)?;
+                let did_document = resolve_did(ctx.accounts.did_record.as_ref(), &did)?;
+                let resolved = did_document.is_some();
+
                 emit!(DIDResolutionEvent {
                     request_id,
                     did: VecToString::try_into(did)?,
-                    resolved: true,
+                    resolved,
                 });
                 
-                // Store response for later use
-                let _response_payload = serialize(&MessagePayload {
-                    msg_type: MessageType::DIDResolutionResponse,
-                    data: serialize(&DIDResolutionResponse { 
-                        request_id, 
-                        resolved: true,
-                        did_document: Vec::new(), // Placeholder
-                    })?,
-                    timestamp: Clock::get()?.unix_timestamp as u64,
-                    message_id: solana_program::hash::hash(&serialize(&request_id)?).to_bytes(),
-                })?;
+                ctx.accounts.post_response(
+                    response_payload(
+                        MessageType::DIDResolutionResponse,
+                        request_id,
+                        source_chain,
+                        response_sequence,
+                        DIDResolutionResponse {
+                            request_id,
+                            resolved,
+                            did_document: did_document.unwrap_or_default(),
+                        }
+                        .encode(),
+                    )?,
+                    &ctx.bumps,
+                )?;
             }
             _ => return Err(ErrorCode::InvalidMessageType.into()),
         }
         Ok(())
     }
     
-    // Store a credential hash and mark it as valid
-    pub fn store_credential(ctx: Context<StoreCredential>, credential_hash: [u8; 32]) -> Result<()> {
+    // Issue a credential of the given schema to `subject` and mark it as
+    // active. The signer is recorded as the issuer. `expires_at` is a unix
+    // timestamp, or 0 for a credential that never expires
+    pub fn store_credential(
+        ctx: Context<StoreCredential>,
+        credential_hash: [u8; 32],
+        subject: Pubkey,
+        expires_at: u64,
+    ) -> Result<()> {
+        let schema = &ctx.accounts.schema;
+        let credential_type = schema.credential_type;
+        ctx.accounts.trusted_issuer.authorize(credential_type)?;
+
         let state = &mut ctx.accounts.state;
         let credential = &mut ctx.accounts.credential;
-        
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(expires_at == 0 || expires_at > now, ErrorCode::InvalidExpiration);
+
         credential.hash = credential_hash;
-        credential.is_valid = true;
-        credential.owner = ctx.accounts.authority.key();
+        credential.schema = schema.key();
+        credential.credential_type = credential_type;
+        credential.status = CredentialStatus::Active;
+        credential.issuer = ctx.accounts.authority.key();
+        credential.subject = subject;
+        credential.revoker = None;
         credential.revocation_date = 0; // Not revoked
+        credential.issued_at = now;
+        credential.expires_at = expires_at;
         
         state.credential_count += 1;
         
         emit!(CredentialStoredEvent {
             credential_pubkey: credential.key(),
             credential_hash,
-            owner: ctx.accounts.authority.key(),
+            schema: credential.schema,
+            credential_type,
+            issuer: credential.issuer,
+            subject,
         });
         
         Ok(())
@@ -171,263 +427,3328 @@ pub mod identity_program {
     pub fn revoke_credential(ctx: Context<RevokeCredential>) -> Result<()> {
         let credential = &mut ctx.accounts.credential;
         
-        // Only the owner can revoke
+        // Only the issuer or its delegated revoker can revoke
         require!(
-            credential.owner == ctx.accounts.authority.key(),
+            credential.can_revoke(&ctx.accounts.authority.key()),
             ErrorCode::Unauthorized
         );
-        
+
+        require!(
+            credential.status != CredentialStatus::Revoked,
+            ErrorCode::InvalidCredentialStatus
+        );
+
         // Mark as revoked with current timestamp
-        credential.is_valid = false;
+        credential.status = CredentialStatus::Revoked;
         credential.revocation_date = Clock::get()?.unix_timestamp as u64;
         
         emit!(CredentialRevokedEvent {
             credential_pubkey: credential.key(),
             credential_hash: credential.hash,
+            issuer: credential.issuer,
+            subject: credential.subject,
+            revoked_by: ctx.accounts.authority.key(),
             revocation_date: credential.revocation_date,
         });
         
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = authority, space = 8 + 32 + 8 + 8)]
-    pub state: Account<'info, ProgramState>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Let another key revoke, suspend and reinstate on the issuer's behalf;
+    // `None` removes the delegation
+    pub fn set_credential_revoker(ctx: Context<SetCredentialRevoker>, revoker: Option<Pubkey>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
 
-#[derive(Accounts)]
-pub struct ReceiveMessage<'info> {
-    #[account(mut)]
-    pub state: Account<'info, ProgramState>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    // Use the Pubkey constant
-    #[account(address = wormhole_constants::WORMHOLE_PROGRAM_ID)]
-    pub wormhole_program: AccountInfo<'info>,
-    pub system_program: Program<'info, System>,
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub recipient: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-}
+        // Only the issuer can delegate
+        require!(
+            credential.issuer == ctx.accounts.authority.key(),
+            ErrorCode::Unauthorized
+        );
 
-#[derive(Accounts)]
-pub struct StoreCredential<'info> {
-    #[account(mut)]
-    pub state: Account<'info, ProgramState>,
-    #[account(init, payer = authority, space = 8 + 32 + 1 + 32 + 8)]
-    pub credential: Account<'info, Credential>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        credential.revoker = revoker;
 
-#[derive(Accounts)]
-pub struct RevokeCredential<'info> {
-    #[account(mut)]
-    pub credential: Account<'info, Credential>,
-    pub authority: Signer<'info>,
-}
+        emit!(CredentialRevokerChangedEvent {
+            credential_pubkey: credential.key(),
+            credential_hash: credential.hash,
+            revoker,
+        });
 
-#[account]
-pub struct ProgramState {
-    pub authority: Pubkey,
-    pub verification_count: u64,
-    pub credential_count: u64,
-}
+        Ok(())
+    }
 
-#[account]
-pub struct Credential {
-    pub hash: [u8; 32],
-    pub is_valid: bool,
-    pub owner: Pubkey,
-    pub revocation_date: u64,
-}
+    // Subject proves it holds an active credential. For a credential issued
+    // to a DID, any controller of that DID presents it on the DID's behalf
+    pub fn present_credential(ctx: Context<PresentCredential>) -> Result<()> {
+        let credential = &ctx.accounts.credential;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub enum MessageType {
-    Verification,
-    VerificationResponse,
-    AssetCreation,
-    TokenTransfer,
-    TokenTransferResponse,
-    CredentialVerification,
-    CredentialVerificationResponse,
-    RoleSynchronization,
-    RoleSyncResponse,
-    DIDResolution,
-    DIDResolutionResponse,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct MessagePayload {
-    pub msg_type: MessageType,
-    pub data: Vec<u8>,
-    pub timestamp: u64,
-    pub message_id: [u8; 32],
-}
+        // Only the subject, or a controller of the subject DID, can present
+        let signer = ctx.accounts.subject.key();
+        let authorized = match &ctx.accounts.did_record {
+            Some(did_record) => {
+                credential.subject == did_record.key()
+                    && !did_record.deactivated
+                    && did_record.is_controlled_by(&signer)
+            }
+            None => credential.subject == signer,
+        };
+        require!(authorized, ErrorCode::Unauthorized);
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct VerificationResponse {
-    pub request_id: u64,
-    pub verified: bool,
-}
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(credential.is_active(now), ErrorCode::InvalidCredentialStatus);
+        ctx.accounts.trusted_issuer.authorize(credential.credential_type)?;
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct TokenTransferResponse {
-    pub transfer_id: u64,
-    pub success: bool,
-}
+        emit!(CredentialPresentedEvent {
+            credential_pubkey: credential.key(),
+            credential_hash: credential.hash,
+            issuer: credential.issuer,
+            subject: credential.subject,
+        });
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct CredentialVerificationResponse {
-    pub request_id: u64,
-    pub verified: bool,
-}
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct RoleSyncResponse {
-    pub request_id: u64,
-    pub success: bool,
-}
+    // Temporarily take an active credential out of use
+    pub fn suspend_credential(ctx: Context<UpdateCredentialStatus>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct DIDResolutionResponse {
-    pub request_id: u64,
-    pub resolved: bool,
-    pub did_document: Vec<u8>,
-}
+        // Only the issuer or its delegated revoker can suspend
+        require!(
+            credential.can_revoke(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
 
-#[event]
-pub struct VerificationEvent {
-    pub request_id: u64,
-    pub did: String,
-    pub verified: bool,
-}
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(
+            credential.effective_status(now) == CredentialStatus::Active,
+            ErrorCode::InvalidCredentialStatus
+        );
+        credential.status = CredentialStatus::Suspended;
 
-#[event]
-pub struct AssetCreationEvent {
-    pub issuer: Pubkey,
-    pub name: String,
-    pub symbol: String,
-}
+        emit!(CredentialStatusChangedEvent {
+            credential_pubkey: credential.key(),
+            credential_hash: credential.hash,
+            status: credential.status,
+        });
 
-#[event]
-pub struct CredentialVerificationEvent {
-    pub request_id: u64,
-    pub credential_hash: [u8; 32],
-    pub verified: bool,
-}
+        Ok(())
+    }
 
-#[event]
-pub struct RoleSyncEvent {
-    pub request_id: u64,
-    pub role: [u8; 32],
-    pub account: [u8; 32],
-    pub is_grant: bool,
-}
+    // Return a suspended credential to use; it still expires on schedule
+    pub fn reinstate_credential(ctx: Context<UpdateCredentialStatus>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
 
-#[event]
-pub struct DIDResolutionEvent {
-    pub request_id: u64,
-    pub did: String,
-    pub resolved: bool,
-}
+        // Only the issuer or its delegated revoker can reinstate
+        require!(
+            credential.can_revoke(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
 
-#[event]
-pub struct CredentialStoredEvent {
-    pub credential_pubkey: Pubkey,
-    pub credential_hash: [u8; 32],
-    pub owner: Pubkey,
-}
+        require!(
+            credential.status == CredentialStatus::Suspended,
+            ErrorCode::InvalidCredentialStatus
+        );
+        credential.status = CredentialStatus::Active;
 
-#[event]
-pub struct CredentialRevokedEvent {
-    pub credential_pubkey: Pubkey,
-    pub credential_hash: [u8; 32],
-    pub revocation_date: u64,
-}
+        emit!(CredentialStatusChangedEvent {
+            credential_pubkey: credential.key(),
+            credential_hash: credential.hash,
+            status: credential.status,
+        });
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid chain ID")]
-    InvalidChain,
-    #[msg("Invalid message type")]
-    InvalidMessageType,
-    #[msg("String too long")]
-    StringTooLong,
-    #[msg("Unauthorized action")]
-    Unauthorized,
-}
+        Ok(())
+    }
 
-fn deserialize_verification(data: &[u8]) -> Result<(u64, Vec<u8>)> {
-    let request_id = u64::try_from_slice(&data[0..8])?;
-    let did_len = u32::try_from_slice(&data[8..12])? as usize;
-    require!(did_len <= 128, ErrorCode::StringTooLong);
-    let did = data[12..12 + did_len].to_vec();
-    Ok((request_id, did))
-}
+    // Whether `subject` holds a usable credential of the given schema, for
+    // programs that gate access on a credential without reading it themselves
+    pub fn holds_credential(ctx: Context<HoldsCredential>, subject: Pubkey) -> Result<bool> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        let credential = &ctx.accounts.credential;
+        Ok(credential.subject == subject
+            && credential.schema == ctx.accounts.schema.key()
+            && credential.is_active(now)
+            && ctx.accounts.trusted_issuer.allows(credential.credential_type))
+    }
 
-fn deserialize_asset_creation(data: &[u8]) -> Result<(Pubkey, Vec<u8>, Vec<u8>)> {
-    let issuer = Pubkey::try_from_slice(&data[0..32])?;
-    let name_len = u32::try_from_slice(&data[32..36])? as usize;
-    require!(name_len <= 32, ErrorCode::StringTooLong);
-    let name = data[36..36 + name_len].to_vec();
-    let symbol_start = 36 + name_len;
-    let symbol_len = u32::try_from_slice(&data[symbol_start..symbol_start + 4])? as usize;
-    require!(symbol_len <= 10, ErrorCode::StringTooLong);
-    let symbol = data[symbol_start + 4..symbol_start + 4 + symbol_len].to_vec();
-    Ok((issuer, name, symbol))
-}
+    // Current status of a credential, reporting Expired once its expiry has passed
+    pub fn get_credential_status(ctx: Context<GetCredentialStatus>) -> Result<CredentialStatus> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        Ok(ctx.accounts.credential.effective_status(now))
+    }
 
-fn deserialize_token_transfer(data: &[u8]) -> Result<(u64, Pubkey, u64)> {
-    let transfer_id = u64::try_from_slice(&data[0..8])?;
-    let token_address = Pubkey::try_from_slice(&data[8..40])?;
-    let amount = u64::try_from_slice(&data[40..48])?;
-    Ok((transfer_id, token_address, amount))
-}
+    // Move a credential created at a client-chosen address onto its
+    // hash-derived address, closing the old account. Legacy credentials carry
+    // no schema, so the issuer names one and must be trusted for its type
+    pub fn migrate_credential(ctx: Context<MigrateCredential>, credential_hash: [u8; 32]) -> Result<()> {
+        let schema = &ctx.accounts.schema;
+        let credential_type = schema.credential_type;
+        ctx.accounts.trusted_issuer.authorize(credential_type)?;
 
-fn deserialize_credential_verification(data: &[u8]) -> Result<(u64, [u8; 32])> {
-    let request_id = u64::try_from_slice(&data[0..8])?;
-    let mut credential_hash = [0u8; 32];
-    credential_hash.copy_from_slice(&data[8..40]);
-    Ok((request_id, credential_hash))
-}
+        let legacy_info = ctx.accounts.legacy_credential.to_account_info();
+        let legacy = LegacyCredential::load(&legacy_info)?;
+        require!(legacy.hash == credential_hash, ErrorCode::InvalidCredentialAccount);
 
-fn deserialize_role_sync(data: &[u8]) -> Result<(u64, [u8; 32], [u8; 32], bool)> {
-    let request_id = u64::try_from_slice(&data[0..8])?;
-    let mut role = [0u8; 32];
-    role.copy_from_slice(&data[8..40]);
-    let mut account = [0u8; 32];
-    account.copy_from_slice(&data[40..72]);
-    let is_grant = data[72] != 0;
-    Ok((request_id, role, account, is_grant))
-}
+        // Only the owner can migrate
+        require!(
+            legacy.owner == ctx.accounts.authority.key(),
+            ErrorCode::Unauthorized
+        );
 
-fn serialize<T: AnchorSerialize>(data: &T) -> Result<Vec<u8>> {
-    Ok(data.try_to_vec()?)
-}
+        let credential = &mut ctx.accounts.credential;
+        credential.hash = legacy.hash;
+        credential.schema = schema.key();
+        credential.credential_type = credential_type;
+        credential.status = if legacy.is_valid {
+            CredentialStatus::Active
+        } else {
+            CredentialStatus::Revoked
+        };
+        // The legacy owner was both issuer and holder
+        credential.issuer = legacy.owner;
+        credential.subject = legacy.owner;
+        credential.revoker = None;
+        credential.revocation_date = legacy.revocation_date;
+        credential.issued_at = 0; // Not recorded by the legacy layout
+        credential.expires_at = 0;
 
-fn deserialize<T: AnchorDeserialize>(data: &[u8]) -> Result<T> {
-    Ok(T::try_from_slice(data)?)
-}
+        close_account(legacy_info, ctx.accounts.authority.to_account_info())?;
 
-impl<'info> ReceiveMessage<'info> {
-    fn into_mint_context(&self) -> CpiContext<'_, '_, '_, 'info, anchor_spl::token::MintTo<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            anchor_spl::token::MintTo {
-                mint: self.token_mint.to_account_info(),
-                to: self.recipient.to_account_info(),
-                authority: self.authority.to_account_info(),
-            },
-        )
+        emit!(CredentialMigratedEvent {
+            legacy_pubkey: ctx.accounts.legacy_credential.key(),
+            credential_pubkey: credential.key(),
+            credential_hash: credential.hash,
+        });
+
+        Ok(())
+    }
+
+    // Create the on-chain record for `did`. A `did:sol` DID can only be
+    // registered by the key it names, which must also control it; DIDs of
+    // other methods are not self-certifying and are registered by a verifier
+    pub fn register_did(ctx: Context<RegisterDid>, did: String, document: DidDocument) -> Result<()> {
+        require!(document.did == did, ErrorCode::InvalidDid);
+        let signer = ctx.accounts.authority.key();
+        match sol_did_key(&did)? {
+            Some(key) => {
+                require_keys_eq!(key, signer, ErrorCode::Unauthorized);
+                require!(document.controllers.contains(&key), ErrorCode::InvalidDidController);
+            }
+            None => require_role(ctx.accounts.role_assignment.as_deref(), &roles::VERIFIER_ROLE, &signer)?,
+        }
+        document.validate()?;
+
+        let now = Clock::get()?.unix_timestamp as u64;
+        let did_record = &mut ctx.accounts.did_record;
+        did_record.did_hash = did_hash(&did);
+        did_record.document = document;
+        did_record.created_at = now;
+        did_record.updated_at = now;
+        did_record.deactivated = false;
+
+        emit!(DidRegisteredEvent {
+            did_record: did_record.key(),
+            did,
+            controller: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    // Replace the verification methods, services and document CID of a DID.
+    // The DID string and its controllers are kept as they are
+    pub fn update_did(
+        ctx: Context<UpdateDid>,
+        _did_hash: [u8; 32],
+        verification_methods: Vec<VerificationMethod>,
+        services: Vec<ServiceEndpoint>,
+        document_cid: Option<String>,
+    ) -> Result<()> {
+        let did_record = &mut ctx.accounts.did_record;
+        require!(
+            did_record.is_controlled_by(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(!did_record.deactivated, ErrorCode::DidDeactivated);
+
+        did_record.document.verification_methods = verification_methods;
+        did_record.document.services = services;
+        did_record.document.document_cid = document_cid;
+        did_record.document.validate()?;
+        did_record.updated_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(DidUpdatedEvent {
+            did_record: did_record.key(),
+            did_hash: did_record.did_hash,
+        });
+
+        Ok(())
+    }
+
+    // Permanently deactivate a DID; it then no longer resolves
+    pub fn deactivate_did(ctx: Context<UpdateDid>, _did_hash: [u8; 32]) -> Result<()> {
+        let did_record = &mut ctx.accounts.did_record;
+        require!(
+            did_record.is_controlled_by(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(!did_record.deactivated, ErrorCode::DidDeactivated);
+
+        did_record.deactivated = true;
+        did_record.updated_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(DidDeactivatedEvent {
+            did_record: did_record.key(),
+            did_hash: did_record.did_hash,
+        });
+
+        Ok(())
+    }
+
+    // Give another key control over a DID, like `addDIDController` on the
+    // EVM side. Controllers can update and deactivate the DID and act for it
+    // as the subject of its credentials
+    pub fn add_did_controller(ctx: Context<UpdateDid>, _did_hash: [u8; 32], controller: Pubkey) -> Result<()> {
+        let did_record = &mut ctx.accounts.did_record;
+        require!(
+            did_record.is_controlled_by(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(!did_record.deactivated, ErrorCode::DidDeactivated);
+        require!(!did_record.is_controlled_by(&controller), ErrorCode::InvalidDidController);
+        require!(
+            did_record.document.controllers.len() < DidDocument::MAX_CONTROLLERS,
+            ErrorCode::TooManyDidEntries
+        );
+
+        did_record.document.controllers.push(controller);
+        did_record.updated_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(DidControllerAddedEvent {
+            did_record: did_record.key(),
+            did: did_record.document.did.clone(),
+            controller,
+            added_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    // Revoke a controller's access to a DID. The last controller cannot be
+    // removed; deactivate the DID instead
+    pub fn remove_did_controller(ctx: Context<UpdateDid>, _did_hash: [u8; 32], controller: Pubkey) -> Result<()> {
+        let did_record = &mut ctx.accounts.did_record;
+        require!(
+            did_record.is_controlled_by(&ctx.accounts.authority.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(!did_record.deactivated, ErrorCode::DidDeactivated);
+        require!(did_record.is_controlled_by(&controller), ErrorCode::InvalidDidController);
+        require!(
+            did_record.document.controllers.len() > 1,
+            ErrorCode::InvalidDidController
+        );
+
+        did_record.document.controllers.retain(|key| *key != controller);
+        did_record.updated_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(DidControllerRemovedEvent {
+            did_record: did_record.key(),
+            did: did_record.document.did.clone(),
+            controller,
+            removed_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    // Whether `controller` may act for the DID, like `isControlledBy` on the EVM side
+    pub fn is_did_controlled_by(ctx: Context<GetDid>, _did_hash: [u8; 32], controller: Pubkey) -> Result<bool> {
+        Ok(ctx.accounts.did_record.is_controlled_by(&controller))
+    }
+
+    // Publish a credential schema. Each (schema_id, version) pair gets its own
+    // account, so existing credentials keep pointing at the version they used
+    pub fn register_schema(
+        ctx: Context<RegisterSchema>,
+        schema_id: u32,
+        version: u16,
+        name: String,
+        credential_type: CredentialType,
+        required_fields: Vec<String>,
+        json_schema_hash: [u8; 32],
+    ) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(name.len() <= CredentialSchema::MAX_NAME_LENGTH, ErrorCode::StringTooLong);
+        require!(
+            required_fields.len() <= CredentialSchema::MAX_FIELDS,
+            ErrorCode::TooManyClaimFields
+        );
+        require!(
+            required_fields
+                .iter()
+                .all(|field| !field.is_empty() && field.len() <= CredentialSchema::MAX_FIELD_LENGTH),
+            ErrorCode::StringTooLong
+        );
+
+        let schema = &mut ctx.accounts.schema;
+        schema.schema_id = schema_id;
+        schema.version = version;
+        schema.name = name;
+        schema.credential_type = credential_type;
+        schema.required_fields = required_fields;
+        schema.json_schema_hash = json_schema_hash;
+
+        emit!(SchemaRegisteredEvent {
+            schema_pubkey: schema.key(),
+            schema_id,
+            version,
+            credential_type,
+            json_schema_hash,
+        });
+
+        Ok(())
+    }
+
+    // Allow `issuer` to store credentials of the listed types
+    pub fn add_trusted_issuer(
+        ctx: Context<AddTrustedIssuer>,
+        issuer: Pubkey,
+        credential_types: Vec<CredentialType>,
+    ) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(
+            !credential_types.is_empty() && credential_types.len() <= TrustedIssuer::MAX_CREDENTIAL_TYPES,
+            ErrorCode::InvalidCredentialTypes
+        );
+
+        let trusted_issuer = &mut ctx.accounts.trusted_issuer;
+        trusted_issuer.issuer = issuer;
+        trusted_issuer.credential_types = credential_types.clone();
+        trusted_issuer.suspended = false;
+
+        emit!(TrustedIssuerAddedEvent {
+            issuer,
+            credential_types,
+        });
+
+        Ok(())
+    }
+
+    // Stop accepting new credentials from an issuer. Its existing credentials
+    // then fail verification until it is reinstated
+    pub fn suspend_trusted_issuer(ctx: Context<UpdateTrustedIssuer>, issuer: Pubkey) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+
+        ctx.accounts.trusted_issuer.suspended = true;
+
+        emit!(TrustedIssuerStatusChangedEvent {
+            issuer,
+            suspended: true,
+        });
+
+        Ok(())
+    }
+
+    pub fn reinstate_trusted_issuer(ctx: Context<UpdateTrustedIssuer>, issuer: Pubkey) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+
+        ctx.accounts.trusted_issuer.suspended = false;
+
+        emit!(TrustedIssuerStatusChangedEvent {
+            issuer,
+            suspended: false,
+        });
+
+        Ok(())
+    }
+
+    // Grant `role` to `account`; the signer must hold the role's admin role.
+    // Granting a role that is already held changes nothing
+    pub fn grant_role(ctx: Context<GrantRole>, role: [u8; 32], account: Pubkey) -> Result<()> {
+        let sender = ctx.accounts.authority.key();
+        let admin_role = role_admin(&ctx.accounts.role_admin, &role)?;
+        require_role(ctx.accounts.admin_assignment.as_deref(), &admin_role, &sender)?;
+
+        let role_assignment = &mut ctx.accounts.role_assignment;
+        if role_assignment.granted_at != 0 {
+            return Ok(());
+        }
+        role_assignment.role = role;
+        role_assignment.account = account;
+        role_assignment.source_chain = wormhole::CHAIN_ID_SOLANA;
+        role_assignment.granted_at = Clock::get()?.unix_timestamp as u64;
+
+        emit!(RoleUpdatedEvent {
+            request_id: 0,
+            role,
+            account,
+            is_grant: true,
+            sender,
+        });
+
+        Ok(())
+    }
+
+    // Revoke `role` from `account`; the signer must hold the role's admin
+    // role. Revoking a role that is not held changes nothing
+    pub fn revoke_role(ctx: Context<RevokeRole>, role: [u8; 32], account: Pubkey) -> Result<()> {
+        let sender = ctx.accounts.authority.key();
+        let admin_role = role_admin(&ctx.accounts.role_admin, &role)?;
+        require_role(ctx.accounts.admin_assignment.as_deref(), &admin_role, &sender)?;
+
+        let role_assignment = &ctx.accounts.role_assignment;
+        if role_assignment.owner != &crate::ID || role_assignment.data_is_empty() {
+            return Ok(());
+        }
+        close_account(role_assignment.to_account_info(), ctx.accounts.authority.to_account_info())?;
+
+        emit!(RoleUpdatedEvent {
+            request_id: 0,
+            role,
+            account,
+            is_grant: false,
+            sender,
+        });
+
+        Ok(())
+    }
+
+    // Give up a role held by the signer. `account` must be the signer, as in
+    // `renounceRole` on the EVM side
+    pub fn renounce_role(ctx: Context<RenounceRole>, role: [u8; 32], account: Pubkey) -> Result<()> {
+        require_keys_eq!(account, ctx.accounts.authority.key(), ErrorCode::Unauthorized);
+
+        emit!(RoleUpdatedEvent {
+            request_id: 0,
+            role,
+            account,
+            is_grant: false,
+            sender: account,
+        });
+
+        Ok(())
+    }
+
+    // Make `admin_role` the role allowed to grant and revoke `role`; the
+    // signer must hold the current admin role
+    pub fn set_role_admin(ctx: Context<SetRoleAdmin>, role: [u8; 32], admin_role: [u8; 32]) -> Result<()> {
+        let sender = ctx.accounts.authority.key();
+        let previous_admin_role = role_admin(&ctx.accounts.role_admin, &role)?;
+        require_role(ctx.accounts.admin_assignment.as_deref(), &previous_admin_role, &sender)?;
+
+        // Created by hand rather than with `init_if_needed`, which would leave
+        // the account without a discriminator until the handler returns
+        let info = ctx.accounts.role_admin.to_account_info();
+        if info.owner != &crate::ID || info.data_is_empty() {
+            create_pda_account(
+                ctx.accounts.authority.to_account_info(),
+                info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+                RoleAdmin::SPACE,
+                ctx.accounts.rent.minimum_balance(RoleAdmin::SPACE),
+                &crate::ID,
+                &[RoleAdmin::SEED_PREFIX, &role, &[ctx.bumps.role_admin]],
+            )?;
+        }
+        RoleAdmin { role, admin_role }.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(RoleAdminChangedEvent {
+            role,
+            previous_admin_role,
+            new_admin_role: admin_role,
+        });
+
+        Ok(())
+    }
+
+    // Publish the current state of (`role`, `account`) to the other chains
+    // as a RoleSynchronization message, addressed to the EVM address linked
+    // to `account`
+    pub fn propagate_role(ctx: Context<PropagateRole>, role: [u8; 32], account: Pubkey) -> Result<()> {
+        let sender = ctx.accounts.authority.key();
+        let admin_role = role_admin(&ctx.accounts.role_admin, &role)?;
+        require_role(ctx.accounts.admin_assignment.as_deref(), &admin_role, &sender)?;
+
+        let address = ctx.accounts.address_link.foreign_address;
+
+        let role_assignment = &ctx.accounts.role_assignment;
+        let is_grant = role_assignment.owner == &crate::ID && !role_assignment.data_is_empty();
+
+        // The outbound sequence doubles as the request id, which is unique
+        // for messages from this emitter
+        let request_id = ctx.accounts.wormhole.wormhole_sequence.value();
+        let data = AbiEncoder::new()
+            .push_uint(request_id.into())
+            .push_bytes32(&role)
+            .push_address(&address)
+            .push_bool(is_grant)
+            .finish();
+        ctx.accounts.wormhole.post(
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            response_payload(
+                MessageType::RoleSynchronization,
+                request_id,
+                ctx.accounts.address_link.chain_id,
+                request_id,
+                data,
+            )?,
+            &ctx.bumps.wormhole,
+        )?;
+
+        emit!(RoleUpdatedEvent {
+            request_id,
+            role,
+            account,
+            is_grant,
+            sender,
+        });
+
+        Ok(())
+    }
+
+    // Link an address on another chain to a Solana account, replacing any
+    // unverified link for that address. Such links are not followed by role
+    // synchronization, which needs the foreign address's own signature
+    pub fn set_address_link(
+        ctx: Context<SetAddressLink>,
+        chain_id: u16,
+        foreign_address: [u8; 20],
+        account: Pubkey,
+    ) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(
+            chain_id != wormhole::CHAIN_ID_SOLANA && foreign_address != [0u8; 20],
+            ErrorCode::InvalidAddressLink
+        );
+
+        // Links proven by the address owner are left to `link_foreign_address`
+        let address_link = &mut ctx.accounts.address_link;
+        require!(!address_link.verified, ErrorCode::AddressAlreadyLinked);
+        address_link.chain_id = chain_id;
+        address_link.foreign_address = foreign_address;
+        address_link.account = account;
+        address_link.linked_at = Clock::get()?.unix_timestamp as u64;
+        address_link.verified = false;
+
+        emit!(AddressLinkedEvent {
+            chain_id,
+            foreign_address,
+            account,
+            verified: false,
+        });
+
+        Ok(())
+    }
+
+    // Link the signer to an EVM address it controls. `signature` is a
+    // `personal_sign` signature by that address over `link_message`, so the
+    // link is proven on both chains without a credential manager
+    pub fn link_foreign_address(
+        ctx: Context<LinkForeignAddress>,
+        chain_id: u16,
+        foreign_address: [u8; 20],
+        signature: [u8; 65],
+    ) -> Result<()> {
+        let account = ctx.accounts.authority.key();
+        require!(
+            chain_id != wormhole::CHAIN_ID_SOLANA,
+            ErrorCode::InvalidAddressLink
+        );
+        let message = link_message(chain_id, &foreign_address, &account);
+        require!(
+            recover_eth_address(&eth_message_hash(&message), &signature)? == foreign_address,
+            ErrorCode::InvalidLinkSignature
+        );
+
+        // A verified link can only be replaced by proving the address again
+        // from the same account
+        let address_link = &ctx.accounts.address_link;
+        require!(
+            !address_link.verified || address_link.account == account,
+            ErrorCode::AddressAlreadyLinked
+        );
+
+        // The account has one identity per chain, so linking a new address
+        // on that chain unlinks the previous one
+        let previous_address = ctx.accounts.chain_identity.foreign_address;
+        if previous_address != [0u8; 20] && previous_address != foreign_address {
+            let previous_link = ctx
+                .accounts
+                .previous_link
+                .as_ref()
+                .ok_or_else(|| error!(ErrorCode::InvalidAddressLink))?;
+            let (expected, _) = Pubkey::find_program_address(
+                &[AddressLink::SEED_PREFIX, &chain_id.to_le_bytes(), &previous_address],
+                &crate::ID,
+            );
+            require_keys_eq!(previous_link.key(), expected, ErrorCode::InvalidAddressLink);
+            if previous_link.account == account {
+                close_account(previous_link.to_account_info(), ctx.accounts.authority.to_account_info())?;
+                emit!(AddressUnlinkedEvent {
+                    chain_id,
+                    foreign_address: previous_address,
+                    account,
+                });
+            }
+        }
+
+        let address_link = &mut ctx.accounts.address_link;
+        let linked_at = Clock::get()?.unix_timestamp as u64;
+        address_link.chain_id = chain_id;
+        address_link.foreign_address = foreign_address;
+        address_link.account = account;
+        address_link.linked_at = linked_at;
+        address_link.verified = true;
+
+        let chain_identity = &mut ctx.accounts.chain_identity;
+        chain_identity.account = account;
+        chain_identity.chain_id = chain_id;
+        chain_identity.foreign_address = foreign_address;
+        chain_identity.linked_at = linked_at;
+
+        emit!(AddressLinkedEvent {
+            chain_id,
+            foreign_address,
+            account,
+            verified: true,
+        });
+
+        Ok(())
+    }
+
+    // Remove the link of an address on another chain. A verified link is
+    // also removed from the linked account's chain identity
+    pub fn remove_address_link(ctx: Context<RemoveAddressLink>, chain_id: u16, foreign_address: [u8; 20]) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::CREDENTIAL_MANAGER_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+
+        let address_link = &ctx.accounts.address_link;
+        if address_link.verified {
+            let chain_identity = ctx
+                .accounts
+                .chain_identity
+                .as_ref()
+                .ok_or_else(|| error!(ErrorCode::InvalidAddressLink))?;
+            let (expected, _) = Pubkey::find_program_address(
+                &[ChainIdentity::SEED_PREFIX, address_link.account.as_ref(), &chain_id.to_le_bytes()],
+                &crate::ID,
+            );
+            require_keys_eq!(chain_identity.key(), expected, ErrorCode::InvalidAddressLink);
+            if chain_identity.foreign_address == foreign_address {
+                close_account(chain_identity.to_account_info(), ctx.accounts.authority.to_account_info())?;
+            }
+        }
+
+        emit!(AddressUnlinkedEvent {
+            chain_id,
+            foreign_address,
+            account: ctx.accounts.address_link.account,
+        });
+
+        Ok(())
+    }
+
+    // Register `token_mint` as the Solana side of `token_address` on
+    // `chain_id`, like `registerCommodityToken`. An asset cannot be moved to
+    // another mint once registered, as tokens may already be in flight
+    pub fn register_asset(
+        ctx: Context<RegisterAsset>,
+        chain_id: u16,
+        token_address: [u8; 20],
+        foreign_decimals: u8,
+    ) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::BRIDGE_ADMIN_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(
+            chain_id != wormhole::CHAIN_ID_SOLANA && token_address != [0u8; 20],
+            ErrorCode::InvalidAssetRegistration
+        );
+
+        let mint = ctx.accounts.token_mint.key();
+        let decimals = ctx.accounts.token_mint.decimals;
+        let wrapped_asset = &mut ctx.accounts.wrapped_asset;
+        wrapped_asset.chain_id = chain_id;
+        wrapped_asset.token_address = token_address;
+        wrapped_asset.mint = mint;
+        wrapped_asset.foreign_decimals = foreign_decimals;
+        wrapped_asset.decimals = decimals;
+
+        emit!(AssetRegisteredEvent {
+            chain_id,
+            token_address,
+            mint,
+            foreign_decimals,
+            decimals,
+        });
+
+        Ok(())
+    }
+
+    // Send `amount` tokens to `recipient` on the chain of the registered
+    // asset, like `bridgeTokens` on Polygon. Wrapped tokens are burned;
+    // tokens native to Solana are locked in the mint's custody account
+    pub fn bridge_out(ctx: Context<BridgeOut>, amount: u64, recipient: [u8; 20]) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidTransferAmount);
+        require!(recipient != [0u8; 20], ErrorCode::InvalidRecipient);
+        let wrapped_asset = &ctx.accounts.wrapped_asset;
+        let token_address = wrapped_asset.token_address;
+        let foreign_amount = to_foreign_amount(amount, wrapped_asset.decimals, wrapped_asset.foreign_decimals)?;
+        let accounts = &ctx.accounts;
+        if accounts.token_mint.mint_authority == COption::Some(accounts.token_authority.key()) {
+            anchor_spl::token::burn(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    anchor_spl::token::Burn {
+                        mint: accounts.token_mint.to_account_info(),
+                        from: accounts.source.to_account_info(),
+                        authority: accounts.authority.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        } else {
+            let custody = accounts
+                .custody
+                .as_ref()
+                .ok_or_else(|| error!(ErrorCode::InvalidCustodyAccount))?;
+            anchor_spl::token::transfer_checked(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    anchor_spl::token::TransferChecked {
+                        from: accounts.source.to_account_info(),
+                        mint: accounts.token_mint.to_account_info(),
+                        to: custody.to_account_info(),
+                        authority: accounts.authority.to_account_info(),
+                    },
+                ),
+                amount,
+                accounts.token_mint.decimals,
+            )?;
+        }
+
+        let transfer_id = ctx.accounts.wormhole.wormhole_sequence.value();
+        let mint = ctx.accounts.token_mint.key();
+        let sender = ctx.accounts.authority.key();
+        let transfer_record = &mut ctx.accounts.transfer_record;
+        transfer_record.transfer_id = transfer_id;
+        transfer_record.source_chain = wormhole::CHAIN_ID_SOLANA;
+        transfer_record.mint = mint;
+        transfer_record.token_address = token_address;
+        transfer_record.amount = amount;
+        transfer_record.account = sender;
+        transfer_record.created_at = Clock::get()?.unix_timestamp as u64;
+
+        let data = AbiEncoder::new()
+            .push_uint(transfer_id.into())
+            .push_address(&token_address)
+            .push_bytes32(&foreign_amount)
+            .push_bytes32(&abi::address_word(&recipient))
+            .finish();
+        ctx.accounts.wormhole.post(
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            response_payload(
+                MessageType::TokenTransfer,
+                transfer_id,
+                ctx.accounts.wrapped_asset.chain_id,
+                transfer_id,
+                data,
+            )?,
+            &ctx.bumps.wormhole,
+        )?;
+
+        emit!(TokenTransferInitiatedEvent {
+            transfer_id,
+            token_address,
+            mint,
+            amount,
+            sender,
+            recipient,
+        });
+
+        Ok(())
+    }
+
+    // Whether `account` holds `role`, like `hasRole` on the EVM side
+    pub fn has_role(ctx: Context<HasRole>, _role: [u8; 32], _account: Pubkey) -> Result<bool> {
+        let role_assignment = &ctx.accounts.role_assignment;
+        Ok(role_assignment.owner == &crate::ID && !role_assignment.data_is_empty())
+    }
+
+    // Admin role of `role`, like `getRoleAdmin` on the EVM side
+    pub fn get_role_admin(ctx: Context<GetRoleAdmin>, role: [u8; 32]) -> Result<[u8; 32]> {
+        role_admin(&ctx.accounts.role_admin, &role)
+    }
+
+    // Trust a Wormhole emitter on a foreign chain as a message source
+    pub fn register_emitter(ctx: Context<RegisterEmitter>, chain: u16, address: [u8; 32]) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::DEFAULT_ADMIN_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(
+            chain != 0 && chain != wormhole::CHAIN_ID_SOLANA,
+            ErrorCode::InvalidChain
+        );
+        require!(address != [0u8; 32], ErrorCode::InvalidEmitterAddress);
+
+        let foreign_emitter = &mut ctx.accounts.foreign_emitter;
+        foreign_emitter.chain = chain;
+        foreign_emitter.address = address;
+
+        emit!(EmitterRegisteredEvent { chain, address });
+
+        Ok(())
+    }
+
+    // Replace the trusted emitter address for an already registered chain
+    pub fn update_emitter(ctx: Context<UpdateEmitter>, chain: u16, address: [u8; 32]) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::DEFAULT_ADMIN_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+        require!(address != [0u8; 32], ErrorCode::InvalidEmitterAddress);
+
+        let previous_address = ctx.accounts.foreign_emitter.address;
+        ctx.accounts.foreign_emitter.address = address;
+
+        emit!(EmitterUpdatedEvent {
+            chain,
+            previous_address,
+            address,
+        });
+
+        Ok(())
+    }
+
+    // Stop accepting messages from a chain; the account rent goes back to the authority
+    pub fn remove_emitter(ctx: Context<RemoveEmitter>, chain: u16) -> Result<()> {
+        require_role(
+            ctx.accounts.role_assignment.as_deref(),
+            &roles::DEFAULT_ADMIN_ROLE,
+            &ctx.accounts.authority.key(),
+        )?;
+
+        emit!(EmitterRemovedEvent { chain });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProgramState::SPACE,
+        seeds = [ProgramState::SEED_PREFIX],
+        bump
+    )]
+    pub state: Account<'info, ProgramState>,
+    #[account(
+        init,
+        payer = authority,
+        space = RoleAssignment::SPACE,
+        seeds = [RoleAssignment::SEED_PREFIX, &roles::DEFAULT_ADMIN_ROLE, authority.key().as_ref()],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReceiveMessage<'info> {
+    #[account(mut, seeds = [ProgramState::SEED_PREFIX], bump)]
+    pub state: Account<'info, ProgramState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // Owned by the core bridge and derived from the VAA hash, so only a VAA
+    // that passed guardian signature verification can be supplied here
+    #[account(
+        seeds = [wormhole::SEED_PREFIX_POSTED_VAA, &vaa_hash],
+        bump,
+        seeds::program = wormhole_constants::WORMHOLE_PROGRAM_ID
+    )]
+    pub posted_vaa: Account<'info, wormhole::PostedVaa<VaaPayload>>,
+    #[account(
+        seeds = [ForeignEmitter::SEED_PREFIX, &posted_vaa.emitter_chain().to_le_bytes()],
+        bump,
+        constraint = foreign_emitter.address == *posted_vaa.emitter_address() @ ErrorCode::UnregisteredEmitter
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+    #[account(
+        init,
+        payer = authority,
+        space = Received::SPACE,
+        seeds = [
+            Received::SEED_PREFIX,
+            &posted_vaa.emitter_chain().to_le_bytes(),
+            posted_vaa.emitter_address(),
+            &posted_vaa.sequence().to_le_bytes()
+        ],
+        bump
+    )]
+    pub received: Account<'info, Received>,
+    pub system_program: Program<'info, System>,
+    // Token transfer accounts, only for token transfers. `recipient` must be
+    // owned by the payload's recipient
+    #[account(mut)]
+    pub token_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub recipient: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    // Only for token transfers; registry entry of the transferred token,
+    // checked against the payload and `token_mint`
+    pub wrapped_asset: Option<Account<'info, WrappedAsset>>,
+    /// CHECK: only for token transfers; holds no data, mint authority of
+    /// wrapped tokens and owner of custody accounts
+    #[account(seeds = [TransferRecord::AUTHORITY_SEED], bump)]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for transfers of tokens native to Solana; the custody
+    /// account of `token_mint`, checked on release
+    #[account(mut)]
+    pub custody: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for token transfers; `TransferRecord` at
+    /// `[TransferRecord::SEED_PREFIX, chain, transfer_id]`, checked on creation
+    #[account(mut)]
+    pub transfer_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for verification and credential verification; must be
+    /// the hash-derived credential address, checked against the decoded payload
+    pub credential: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for verification and credential verification; must be
+    /// the registry entry of the stored credential's issuer, checked once the
+    /// credential is read
+    pub trusted_issuer: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for verification and DID resolution; must be the record
+    /// derived from the requested DID, checked against the decoded payload
+    pub did_record: Option<UncheckedAccount<'info>>,
+    // Soulbound identity token accounts, only for verification. Their
+    // addresses derive from the verified DID and are checked on issuance
+    /// CHECK: Token-2022 mint at `[IdentityToken::MINT_SEED_PREFIX, did_hash]`
+    #[account(mut)]
+    pub identity_mint: Option<UncheckedAccount<'info>>,
+    /// CHECK: `IdentityToken` record at `[IdentityToken::SEED_PREFIX, did_hash]`
+    #[account(mut)]
+    pub identity_token: Option<UncheckedAccount<'info>>,
+    /// CHECK: the DID's primary controller, which receives the token
+    pub identity_holder: Option<UncheckedAccount<'info>>,
+    /// CHECK: the holder's Token-2022 associated token account for the mint
+    #[account(mut)]
+    pub identity_holder_token_account: Option<UncheckedAccount<'info>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    /// CHECK: only for verification; `VerificationRecord` at
+    /// `[VerificationRecord::SEED_PREFIX, chain, request_id]`, checked on creation
+    #[account(mut)]
+    pub verification_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: only for role synchronization; `RoleAssignment` at
+    /// `[RoleAssignment::SEED_PREFIX, role, account]`, checked against the payload
+    #[account(mut)]
+    pub role_assignment: Option<UncheckedAccount<'info>>,
+    // Only for role synchronization; link of the synced EVM address on the
+    // emitter chain, checked against the payload
+    pub address_link: Option<Account<'info, AddressLink>>,
+    // Core bridge accounts needed to post the response message
+    pub wormhole: PostWormholeMessage<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWormhole<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = WormholeConfig::SPACE,
+        seeds = [WormholeConfig::SEED_PREFIX],
+        bump
+    )]
+    pub wormhole_config: Account<'info, WormholeConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = WormholeEmitter::SPACE,
+        seeds = [WormholeEmitter::SEED_PREFIX],
+        bump
+    )]
+    pub wormhole_emitter: Account<'info, WormholeEmitter>,
+    #[account(address = wormhole_constants::WORMHOLE_PROGRAM_ID)]
+    pub wormhole_program: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [wormhole::BridgeData::SEED_PREFIX],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub wormhole_bridge: Account<'info, wormhole::BridgeData>,
+    #[account(
+        mut,
+        seeds = [wormhole::FeeCollector::SEED_PREFIX],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub wormhole_fee_collector: Account<'info, wormhole::FeeCollector>,
+    /// CHECK: emitter sequence tracker, created by the core bridge on the first post
+    #[account(
+        mut,
+        seeds = [wormhole::SequenceTracker::SEED_PREFIX, wormhole_emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub wormhole_sequence: UncheckedAccount<'info>,
+    /// CHECK: initialized by the core bridge when the initial message is posted
+    #[account(
+        mut,
+        seeds = [
+            wormhole_constants::SEED_PREFIX_SENT,
+            &current_sequence(&wormhole_sequence).to_le_bytes()
+        ],
+        bump
+    )]
+    pub wormhole_message: UncheckedAccount<'info>,
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct NextSequence<'info> {
+    #[account(seeds = [WormholeConfig::SEED_PREFIX], bump)]
+    pub wormhole_config: Account<'info, WormholeConfig>,
+    #[account(address = wormhole_config.sequence)]
+    pub wormhole_sequence: Account<'info, wormhole::SequenceTracker>,
+}
+
+#[derive(Accounts)]
+#[instruction(credential_hash: [u8; 32])]
+pub struct StoreCredential<'info> {
+    #[account(mut, seeds = [ProgramState::SEED_PREFIX], bump)]
+    pub state: Account<'info, ProgramState>,
+    // One credential per hash, addressable on-chain from the hash alone
+    #[account(
+        init,
+        payer = authority,
+        space = Credential::SPACE,
+        seeds = [Credential::SEED_PREFIX, &credential_hash],
+        bump
+    )]
+    pub credential: Account<'info, Credential>,
+    pub schema: Account<'info, CredentialSchema>,
+    #[account(
+        seeds = [TrustedIssuer::SEED_PREFIX, authority.key().as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCredential<'info> {
+    #[account(mut)]
+    pub credential: Account<'info, Credential>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCredentialStatus<'info> {
+    #[account(mut)]
+    pub credential: Account<'info, Credential>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCredentialRevoker<'info> {
+    #[account(mut)]
+    pub credential: Account<'info, Credential>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PresentCredential<'info> {
+    pub credential: Account<'info, Credential>,
+    #[account(
+        seeds = [TrustedIssuer::SEED_PREFIX, credential.issuer.as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+    // Required when the credential was issued to a DID rather than a wallet
+    pub did_record: Option<Account<'info, DidRecord>>,
+    pub subject: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct HoldsCredential<'info> {
+    pub credential: Account<'info, Credential>,
+    pub schema: Account<'info, CredentialSchema>,
+    #[account(
+        seeds = [TrustedIssuer::SEED_PREFIX, credential.issuer.as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+}
+
+#[derive(Accounts)]
+pub struct GetCredentialStatus<'info> {
+    pub credential: Account<'info, Credential>,
+}
+
+#[derive(Accounts)]
+#[instruction(credential_hash: [u8; 32])]
+pub struct MigrateCredential<'info> {
+    /// CHECK: still in the pre-lifecycle layout, parsed by `LegacyCredential::load`
+    #[account(mut, owner = crate::ID)]
+    pub legacy_credential: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = Credential::SPACE,
+        seeds = [Credential::SEED_PREFIX, &credential_hash],
+        bump
+    )]
+    pub credential: Account<'info, Credential>,
+    pub schema: Account<'info, CredentialSchema>,
+    #[account(
+        seeds = [TrustedIssuer::SEED_PREFIX, authority.key().as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String)]
+pub struct RegisterDid<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = DidRecord::SPACE,
+        seeds = [DidRecord::SEED_PREFIX, &did_hash(&did)],
+        bump
+    )]
+    pub did_record: Account<'info, DidRecord>,
+    // Only for DIDs of other methods; signer's assignment of VERIFIER_ROLE
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did_hash: [u8; 32])]
+pub struct UpdateDid<'info> {
+    #[account(
+        mut,
+        seeds = [DidRecord::SEED_PREFIX, &did_hash],
+        bump
+    )]
+    pub did_record: Account<'info, DidRecord>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(did_hash: [u8; 32])]
+pub struct GetDid<'info> {
+    #[account(seeds = [DidRecord::SEED_PREFIX, &did_hash], bump)]
+    pub did_record: Account<'info, DidRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema_id: u32, version: u16)]
+pub struct RegisterSchema<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        init,
+        payer = authority,
+        space = CredentialSchema::SPACE,
+        seeds = [CredentialSchema::SEED_PREFIX, &schema_id.to_le_bytes(), &version.to_le_bytes()],
+        bump
+    )]
+    pub schema: Account<'info, CredentialSchema>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(issuer: Pubkey)]
+pub struct AddTrustedIssuer<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        init,
+        payer = authority,
+        space = TrustedIssuer::SPACE,
+        seeds = [TrustedIssuer::SEED_PREFIX, issuer.as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(issuer: Pubkey)]
+pub struct UpdateTrustedIssuer<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        mut,
+        seeds = [TrustedIssuer::SEED_PREFIX, issuer.as_ref()],
+        bump
+    )]
+    pub trusted_issuer: Account<'info, TrustedIssuer>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32], account: Pubkey)]
+pub struct GrantRole<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RoleAssignment::SPACE,
+        seeds = [RoleAssignment::SEED_PREFIX, &role, account.as_ref()],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    /// CHECK: may not exist; the address is checked by `role_admin`
+    pub role_admin: UncheckedAccount<'info>,
+    // Signer's assignment of the role's admin role
+    pub admin_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32], account: Pubkey)]
+pub struct RevokeRole<'info> {
+    /// CHECK: may not exist, in which case there is nothing to revoke
+    #[account(mut, seeds = [RoleAssignment::SEED_PREFIX, &role, account.as_ref()], bump)]
+    pub role_assignment: UncheckedAccount<'info>,
+    /// CHECK: may not exist; the address is checked by `role_admin`
+    pub role_admin: UncheckedAccount<'info>,
+    // Signer's assignment of the role's admin role
+    pub admin_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32], account: Pubkey)]
+pub struct RenounceRole<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [RoleAssignment::SEED_PREFIX, &role, account.as_ref()],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32])]
+pub struct SetRoleAdmin<'info> {
+    /// CHECK: may not exist yet; created by the handler
+    #[account(mut, seeds = [RoleAdmin::SEED_PREFIX, &role], bump)]
+    pub role_admin: UncheckedAccount<'info>,
+    // Signer's assignment of the role's current admin role
+    pub admin_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32], account: Pubkey)]
+pub struct PropagateRole<'info> {
+    /// CHECK: may not exist; an empty account is sent as a revocation
+    #[account(seeds = [RoleAssignment::SEED_PREFIX, &role, account.as_ref()], bump)]
+    pub role_assignment: UncheckedAccount<'info>,
+    #[account(constraint = address_link.account == account @ ErrorCode::InvalidAddressLink)]
+    pub address_link: Account<'info, AddressLink>,
+    /// CHECK: may not exist; the address is checked by `role_admin`
+    pub role_admin: UncheckedAccount<'info>,
+    // Signer's assignment of the role's admin role
+    pub admin_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub wormhole: PostWormholeMessage<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16, token_address: [u8; 20])]
+pub struct RegisterAsset<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = WrappedAsset::SPACE,
+        seeds = [WrappedAsset::SEED_PREFIX, &chain_id.to_le_bytes(), &token_address],
+        bump
+    )]
+    pub wrapped_asset: Account<'info, WrappedAsset>,
+    pub token_mint: Account<'info, Mint>,
+    // Signer's assignment of BRIDGE_ADMIN_ROLE
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BridgeOut<'info> {
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    #[account(constraint = wrapped_asset.mint == token_mint.key() @ ErrorCode::UnregisteredAsset)]
+    pub wrapped_asset: Account<'info, WrappedAsset>,
+    #[account(mut, token::mint = token_mint, token::authority = authority)]
+    pub source: Account<'info, TokenAccount>,
+    /// CHECK: holds no data; mint authority of wrapped tokens and owner of
+    /// custody accounts
+    #[account(seeds = [TransferRecord::AUTHORITY_SEED], bump)]
+    pub token_authority: UncheckedAccount<'info>,
+    // Only for tokens native to Solana
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = token_authority,
+        seeds = [TransferRecord::CUSTODY_SEED_PREFIX, token_mint.key().as_ref()],
+        bump
+    )]
+    pub custody: Option<Account<'info, TokenAccount>>,
+    pub wormhole: PostWormholeMessage<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = TransferRecord::SPACE,
+        seeds = [
+            TransferRecord::SEED_PREFIX,
+            &wormhole::CHAIN_ID_SOLANA.to_le_bytes(),
+            &wormhole.wormhole_sequence.value().to_le_bytes()
+        ],
+        bump
+    )]
+    pub transfer_record: Account<'info, TransferRecord>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Core bridge accounts for publishing a message from the program's emitter
+#[derive(Accounts)]
+pub struct PostWormholeMessage<'info> {
+    #[account(address = wormhole_constants::WORMHOLE_PROGRAM_ID)]
+    /// CHECK: the core bridge program
+    pub wormhole_program: AccountInfo<'info>,
+    #[account(mut, seeds = [WormholeConfig::SEED_PREFIX], bump)]
+    pub wormhole_config: Box<Account<'info, WormholeConfig>>,
+    #[account(mut, address = wormhole_config.bridge)]
+    pub wormhole_bridge: Box<Account<'info, wormhole::BridgeData>>,
+    #[account(mut, address = wormhole_config.fee_collector)]
+    pub wormhole_fee_collector: Box<Account<'info, wormhole::FeeCollector>>,
+    #[account(seeds = [WormholeEmitter::SEED_PREFIX], bump = wormhole_emitter.bump)]
+    pub wormhole_emitter: Account<'info, WormholeEmitter>,
+    #[account(mut, address = wormhole_config.sequence)]
+    pub wormhole_sequence: Box<Account<'info, wormhole::SequenceTracker>>,
+    /// CHECK: initialized by the core bridge when the message is posted
+    #[account(
+        mut,
+        seeds = [
+            wormhole_constants::SEED_PREFIX_SENT,
+            &wormhole_sequence.value().to_le_bytes()
+        ],
+        bump
+    )]
+    pub wormhole_message: UncheckedAccount<'info>,
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16, foreign_address: [u8; 20])]
+pub struct SetAddressLink<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AddressLink::SPACE,
+        seeds = [AddressLink::SEED_PREFIX, &chain_id.to_le_bytes(), &foreign_address],
+        bump
+    )]
+    pub address_link: Account<'info, AddressLink>,
+    // Signer's assignment of CREDENTIAL_MANAGER_ROLE
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16, foreign_address: [u8; 20])]
+pub struct LinkForeignAddress<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AddressLink::SPACE,
+        seeds = [AddressLink::SEED_PREFIX, &chain_id.to_le_bytes(), &foreign_address],
+        bump
+    )]
+    pub address_link: Account<'info, AddressLink>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ChainIdentity::SPACE,
+        seeds = [ChainIdentity::SEED_PREFIX, authority.key().as_ref(), &chain_id.to_le_bytes()],
+        bump
+    )]
+    pub chain_identity: Account<'info, ChainIdentity>,
+    // Link of the address the chain identity currently names, when it is
+    // being replaced; closed if it still points at the signer
+    #[account(mut)]
+    pub previous_link: Option<Account<'info, AddressLink>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16, foreign_address: [u8; 20])]
+pub struct RemoveAddressLink<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [AddressLink::SEED_PREFIX, &chain_id.to_le_bytes(), &foreign_address],
+        bump
+    )]
+    pub address_link: Account<'info, AddressLink>,
+    // Only for verified links; chain identity of the linked account, closed
+    // if it still names this address
+    #[account(mut)]
+    pub chain_identity: Option<Account<'info, ChainIdentity>>,
+    // Signer's assignment of CREDENTIAL_MANAGER_ROLE
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: [u8; 32], account: Pubkey)]
+pub struct HasRole<'info> {
+    /// CHECK: may not exist; an empty account means the role is not held
+    #[account(seeds = [RoleAssignment::SEED_PREFIX, &role, account.as_ref()], bump)]
+    pub role_assignment: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetRoleAdmin<'info> {
+    /// CHECK: may not exist; the address is checked by `role_admin`
+    pub role_admin: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct RegisterEmitter<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        init,
+        payer = authority,
+        space = ForeignEmitter::SPACE,
+        seeds = [ForeignEmitter::SEED_PREFIX, &chain.to_le_bytes()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct UpdateEmitter<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        mut,
+        seeds = [ForeignEmitter::SEED_PREFIX, &chain.to_le_bytes()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct RemoveEmitter<'info> {
+    // Signer's assignment of the role the instruction is gated on
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ForeignEmitter::SEED_PREFIX, &chain.to_le_bytes()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+// Program-wide settings, a single account at `[ProgramState::SEED_PREFIX]` so
+// that its authority cannot be substituted
+#[account]
+pub struct ProgramState {
+    pub authority: Pubkey,
+    pub verification_count: u64,
+    pub credential_count: u64,
+}
+
+impl ProgramState {
+    pub const SEED_PREFIX: &'static [u8] = b"state";
+    pub const SPACE: usize = 8 + 32 + 8 + 8;
+}
+
+// Same ordering as `CredentialStatus` in SoulboundNFT.sol. `Expired` is never
+// stored; it is derived from `expires_at` by `Credential::effective_status`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialStatus {
+    Active,
+    Suspended,
+    Revoked,
+    Expired,
+}
+
+// Kinds of claim a credential attests to; trusted issuers are authorized per type
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialType {
+    Kyc,
+    AccreditedInvestor,
+    AmlCleared,
+    Jurisdiction,
+}
+
+// Describes the claims carried by credentials that reference it. The claims
+// themselves stay off-chain; `json_schema_hash` pins the JSON schema they follow
+#[account]
+pub struct CredentialSchema {
+    pub schema_id: u32,
+    pub version: u16,
+    pub name: String,
+    pub credential_type: CredentialType,
+    pub required_fields: Vec<String>,
+    pub json_schema_hash: [u8; 32],
+}
+
+impl CredentialSchema {
+    pub const SEED_PREFIX: &'static [u8] = b"schema";
+    pub const MAX_NAME_LENGTH: usize = 32;
+    pub const MAX_FIELDS: usize = 8;
+    pub const MAX_FIELD_LENGTH: usize = 32;
+    pub const SPACE: usize = 8 + 4 + 2 + 4 + Self::MAX_NAME_LENGTH + 1
+        + 4 + Self::MAX_FIELDS * (4 + Self::MAX_FIELD_LENGTH) + 32;
+}
+
+#[account]
+pub struct Credential {
+    pub hash: [u8; 32],
+    // `CredentialSchema` account the credential was issued under
+    pub schema: Pubkey,
+    // Copied from the schema so verification needs no extra account
+    pub credential_type: CredentialType,
+    pub status: CredentialStatus,
+    // Key that issued the credential
+    pub issuer: Pubkey,
+    // Holder wallet, or the DID account the credential was issued to
+    pub subject: Pubkey,
+    // Optional key allowed to revoke on the issuer's behalf
+    pub revoker: Option<Pubkey>,
+    pub revocation_date: u64,
+    pub issued_at: u64,
+    // 0 for a credential that never expires
+    pub expires_at: u64,
+}
+
+impl Credential {
+    pub const SEED_PREFIX: &'static [u8] = b"credential";
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 1 + 32 + 32 + 33 + 8 + 8 + 8;
+
+    pub fn can_revoke(&self, key: &Pubkey) -> bool {
+        self.issuer == *key || self.revoker.as_ref() == Some(key)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
+    }
+
+    // Status as seen at `now`: revocation is final, otherwise a passed expiry
+    // takes precedence over the stored status
+    pub fn effective_status(&self, now: u64) -> CredentialStatus {
+        match self.status {
+            CredentialStatus::Revoked => CredentialStatus::Revoked,
+            _ if self.is_expired(now) => CredentialStatus::Expired,
+            status => status,
+        }
+    }
+
+    pub fn is_active(&self, now: u64) -> bool {
+        self.effective_status(now) == CredentialStatus::Active
+    }
+}
+
+// Credential layout from before the status lifecycle, kept so that
+// `migrate_credential` can read accounts created at random addresses
+#[derive(AnchorDeserialize)]
+pub struct LegacyCredential {
+    pub hash: [u8; 32],
+    pub is_valid: bool,
+    pub owner: Pubkey,
+    pub revocation_date: u64,
+}
+
+impl LegacyCredential {
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == Credential::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        Ok(Self::deserialize(&mut &data[8..])?)
+    }
+}
+
+// Registry entry for an issuer allowed to store credentials
+#[account]
+pub struct TrustedIssuer {
+    pub issuer: Pubkey,
+    pub credential_types: Vec<CredentialType>,
+    pub suspended: bool,
+}
+
+impl TrustedIssuer {
+    pub const SEED_PREFIX: &'static [u8] = b"trusted_issuer";
+    pub const MAX_CREDENTIAL_TYPES: usize = 4;
+    pub const SPACE: usize = 8 + 32 + 4 + Self::MAX_CREDENTIAL_TYPES + 1;
+
+    pub fn allows(&self, credential_type: CredentialType) -> bool {
+        !self.suspended && self.credential_types.contains(&credential_type)
+    }
+
+    pub fn authorize(&self, credential_type: CredentialType) -> Result<()> {
+        require!(!self.suspended, ErrorCode::IssuerSuspended);
+        require!(
+            self.credential_types.contains(&credential_type),
+            ErrorCode::IssuerNotAuthorized
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationKeyType {
+    Ed25519,
+    Secp256k1,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationMethod {
+    // Fragment identifying the method within the document, e.g. "key-1"
+    pub id: String,
+    pub key_type: VerificationKeyType,
+    // 32-byte Ed25519 key, or a 33/65-byte SEC1 secp256k1 key
+    pub public_key: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ServiceEndpoint {
+    pub id: String,
+    pub service_type: String,
+    pub endpoint: String,
+}
+
+// Contents of a DID document. This is what DID resolution returns, borsh
+// serialized; anything larger than the on-chain limits belongs in the
+// off-chain document referenced by `document_cid`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DidDocument {
+    pub did: String,
+    pub controllers: Vec<Pubkey>,
+    pub verification_methods: Vec<VerificationMethod>,
+    pub services: Vec<ServiceEndpoint>,
+    pub document_cid: Option<String>,
+}
+
+impl DidDocument {
+    pub const MAX_DID_LENGTH: usize = 128;
+    pub const MAX_CONTROLLERS: usize = 4;
+    pub const MAX_VERIFICATION_METHODS: usize = 4;
+    pub const MAX_SERVICES: usize = 4;
+    pub const MAX_ID_LENGTH: usize = 32;
+    pub const MAX_KEY_LENGTH: usize = 65;
+    pub const MAX_ENDPOINT_LENGTH: usize = 128;
+    pub const MAX_CID_LENGTH: usize = 64;
+
+    pub const SPACE: usize = 4 + Self::MAX_DID_LENGTH
+        + 4 + Self::MAX_CONTROLLERS * 32
+        + 4 + Self::MAX_VERIFICATION_METHODS * (4 + Self::MAX_ID_LENGTH + 1 + 4 + Self::MAX_KEY_LENGTH)
+        + 4 + Self::MAX_SERVICES * (4 + Self::MAX_ID_LENGTH + 4 + Self::MAX_ID_LENGTH + 4 + Self::MAX_ENDPOINT_LENGTH)
+        + 1 + 4 + Self::MAX_CID_LENGTH;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.did.starts_with("did:"), ErrorCode::InvalidDid);
+        require!(self.did.len() <= Self::MAX_DID_LENGTH, ErrorCode::StringTooLong);
+        require!(
+            !self.controllers.is_empty()
+                && self.controllers.len() <= Self::MAX_CONTROLLERS
+                && self.verification_methods.len() <= Self::MAX_VERIFICATION_METHODS
+                && self.services.len() <= Self::MAX_SERVICES,
+            ErrorCode::TooManyDidEntries
+        );
+        for (i, controller) in self.controllers.iter().enumerate() {
+            require!(
+                !self.controllers[..i].contains(controller),
+                ErrorCode::InvalidDidController
+            );
+        }
+        for method in &self.verification_methods {
+            require!(
+                !method.id.is_empty() && method.id.len() <= Self::MAX_ID_LENGTH,
+                ErrorCode::StringTooLong
+            );
+            // secp256k1 keys are SEC1 encoded, compressed or uncompressed
+            let key_ok = match method.key_type {
+                VerificationKeyType::Ed25519 => method.public_key.len() == 32,
+                VerificationKeyType::Secp256k1 => matches!(
+                    (method.public_key.len(), method.public_key.first()),
+                    (33, Some(0x02 | 0x03)) | (65, Some(0x04))
+                ),
+            };
+            require!(key_ok, ErrorCode::InvalidVerificationMethod);
+        }
+        for service in &self.services {
+            require!(
+                !service.id.is_empty()
+                    && service.id.len() <= Self::MAX_ID_LENGTH
+                    && service.service_type.len() <= Self::MAX_ID_LENGTH
+                    && service.endpoint.len() <= Self::MAX_ENDPOINT_LENGTH,
+                ErrorCode::StringTooLong
+            );
+        }
+        if let Some(cid) = &self.document_cid {
+            require!(cid.len() <= Self::MAX_CID_LENGTH, ErrorCode::StringTooLong);
+        }
+        Ok(())
+    }
+}
+
+// DID registered on Solana, addressed by the keccak hash of the DID string
+#[account]
+pub struct DidRecord {
+    pub did_hash: [u8; 32],
+    pub document: DidDocument,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub deactivated: bool,
+}
+
+impl DidRecord {
+    pub const SEED_PREFIX: &'static [u8] = b"did";
+    pub const SPACE: usize = 8 + 32 + DidDocument::SPACE + 8 + 8 + 1;
+
+    pub fn is_controlled_by(&self, key: &Pubkey) -> bool {
+        self.document.controllers.contains(key)
+    }
+}
+
+// Membership of `account` in `role`, like `RoleData.members` in
+// MultiChainAccess.sol. The account exists only while the role is held.
+// Roles synced from an EVM chain are held by the linked Solana account.
+#[account]
+pub struct RoleAssignment {
+    pub role: [u8; 32],
+    pub account: Pubkey,
+    // Wormhole chain the grant came from; Solana for local grants
+    pub source_chain: u16,
+    pub granted_at: u64,
+}
+
+impl RoleAssignment {
+    pub const SEED_PREFIX: &'static [u8] = b"role";
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 8;
+}
+
+// Solana account acting for an address on another chain, like
+// `setChainAddress` in IDMapping.sol. Keyed by Wormhole chain id and the
+// 20-byte EVM address, so inbound messages can be routed to the account
+#[account]
+pub struct AddressLink {
+    pub chain_id: u16,
+    pub foreign_address: [u8; 20],
+    pub account: Pubkey,
+    pub linked_at: u64,
+    // Whether both keys signed the link, rather than a credential manager
+    pub verified: bool,
+}
+
+impl AddressLink {
+    pub const SEED_PREFIX: &'static [u8] = b"address_link";
+    pub const SPACE: usize = 8 + 2 + 20 + 32 + 8 + 1;
+}
+
+// Address on another chain proven to belong to `account`, the reverse of a
+// verified `AddressLink`. Mirrors `chainDetails` in SoulboundNFT.sol
+#[account]
+pub struct ChainIdentity {
+    pub account: Pubkey,
+    pub chain_id: u16,
+    pub foreign_address: [u8; 20],
+    pub linked_at: u64,
+}
+
+impl ChainIdentity {
+    pub const SEED_PREFIX: &'static [u8] = b"chain_identity";
+    pub const SPACE: usize = 8 + 32 + 2 + 20 + 8;
+}
+
+// Admin role of `role`, like `RoleData.adminRole`. Roles without an account
+// are administered by `DEFAULT_ADMIN_ROLE`
+#[account]
+pub struct RoleAdmin {
+    pub role: [u8; 32],
+    pub admin_role: [u8; 32],
+}
+
+impl RoleAdmin {
+    pub const SEED_PREFIX: &'static [u8] = b"role_admin";
+    pub const SPACE: usize = 8 + 32 + 32;
+}
+
+// Outcome of a verification request, one per (source chain, request id),
+// mirroring `VerificationRequest` in CrossChainBridge.sol
+#[account]
+pub struct VerificationRecord {
+    pub source_chain: u16,
+    pub request_id: u64,
+    pub did: String,
+    // Holder of the identity token, if the DID verified
+    pub subject: Option<Pubkey>,
+    pub sbt_token_id: u64,
+    pub verified: bool,
+    // Source chain timestamp of the request message
+    pub requested_at: u64,
+    pub processed_at: u64,
+    // Wormhole sequence of the response posted back to the source chain
+    pub response_sequence: u64,
+}
+
+impl VerificationRecord {
+    pub const SEED_PREFIX: &'static [u8] = b"verification";
+    pub const SPACE: usize = 8 + 2 + 8 + 4 + 128 + 33 + 8 + 1 + 8 + 8 + 8;
+}
+
+// Solana mint carrying a token from another chain, one per (chain, token
+// address), like `commodityTokens` in CrossChainBridge.sol
+#[account]
+pub struct WrappedAsset {
+    pub chain_id: u16,
+    pub token_address: [u8; 20],
+    pub mint: Pubkey,
+    // Decimals of the token contract and of the mint
+    pub foreign_decimals: u8,
+    pub decimals: u8,
+}
+
+impl WrappedAsset {
+    pub const SEED_PREFIX: &'static [u8] = b"wrapped_asset";
+    pub const SPACE: usize = 8 + 2 + 20 + 32 + 1 + 1;
+}
+
+// Token transfer between Solana and another chain, one per (source chain,
+// transfer id), mirroring `TokenTransferRequest` in CrossChainBridge.sol.
+// Outbound transfers use Solana as the source chain and the Wormhole
+// sequence of their message as the transfer id
+#[account]
+pub struct TransferRecord {
+    pub transfer_id: u64,
+    pub source_chain: u16,
+    pub mint: Pubkey,
+    // Token contract on the EVM side
+    pub token_address: [u8; 20],
+    pub amount: u64,
+    // Part of an inbound amount below the mint's precision, in the token
+    // contract's decimals. It was not released and is owed to the sender
+    pub dust: [u8; 32],
+    // Recipient of an inbound transfer, sender of an outbound one
+    pub account: Pubkey,
+    pub created_at: u64,
+}
+
+impl TransferRecord {
+    pub const SEED_PREFIX: &'static [u8] = b"transfer";
+    pub const AUTHORITY_SEED: &'static [u8] = b"token_authority";
+    pub const CUSTODY_SEED_PREFIX: &'static [u8] = b"custody";
+    pub const SPACE: usize = 8 + 8 + 2 + 32 + 20 + 8 + 32 + 32 + 8;
+}
+
+// Soulbound identity token minted on Solana for a verified DID, the
+// counterpart of the `SoulboundIdentity` token on the EVM side
+#[account]
+pub struct IdentityToken {
+    pub did_hash: [u8; 32],
+    pub holder: Pubkey,
+    // Token-2022 mint with the NonTransferable extension and a supply of one
+    pub mint: Pubkey,
+    // Wormhole chain and SBT token id of the source-chain identity
+    pub source_chain: u16,
+    pub sbt_token_id: u64,
+    // Hash of the credential the verification was granted on
+    pub credential_hash: [u8; 32],
+    pub issued_at: u64,
+}
+
+impl IdentityToken {
+    pub const SEED_PREFIX: &'static [u8] = b"identity_token";
+    pub const MINT_SEED_PREFIX: &'static [u8] = b"identity_mint";
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 2 + 8 + 32 + 8;
+    pub const NAME: &'static str = "Soulbound Identity";
+    pub const SYMBOL: &'static str = "SBID";
+}
+
+// Core bridge accounts used for outbound messages, recorded at setup
+#[account]
+pub struct WormholeConfig {
+    pub bridge: Pubkey,
+    pub fee_collector: Pubkey,
+    pub sequence: Pubkey,
+    // Batch id for the next posted message, like `nonceCounter` on the EVM side
+    pub nonce: u32,
+}
+
+impl WormholeConfig {
+    pub const SEED_PREFIX: &'static [u8] = b"config";
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 4;
+}
+
+// Program-derived emitter that signs every outbound Wormhole message
+#[account]
+pub struct WormholeEmitter {
+    pub bump: u8,
+}
+
+impl WormholeEmitter {
+    pub const SEED_PREFIX: &'static [u8] = wormhole::SEED_PREFIX_EMITTER;
+    pub const SPACE: usize = 8 + 1;
+}
+
+// Trusted Wormhole emitter for a foreign chain, one per chain id
+#[account]
+pub struct ForeignEmitter {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+impl ForeignEmitter {
+    pub const SEED_PREFIX: &'static [u8] = b"foreign_emitter";
+    pub const SPACE: usize = 8 + 2 + 32;
+}
+
+// Receipt for a processed VAA, one per emitter and sequence
+#[account]
+pub struct Received {
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub vaa_hash: [u8; 32],
+    pub message_id: [u8; 32],
+}
+
+impl Received {
+    pub const SEED_PREFIX: &'static [u8] = b"received";
+    pub const SPACE: usize = 8 + 2 + 8 + 32 + 32;
+}
+
+// Wire values follow the `MessageType` enum in CrossChainBridge.sol,
+// including the response types posted back by this program
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum MessageType {
+    Verification = 0,
+    VerificationResponse = 1,
+    AssetCreation = 2,
+    Custom = 3,
+    TokenTransfer = 4,
+    CredentialVerification = 5,
+    CredentialStatusUpdate = 6,
+    RoleSynchronization = 7,
+    DIDResolution = 8,
+    TokenTransferResponse = 9,
+    CredentialVerificationResponse = 10,
+    RoleSyncResponse = 11,
+    DIDResolutionResponse = 12,
+}
+
+// abi.encode(MessageType, bytes data, uint256 timestamp, bytes32 messageId)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessagePayload {
+    pub msg_type: MessageType,
+    pub data: Vec<u8>,
+    pub timestamp: u64,
+    pub message_id: [u8; 32],
+}
+
+impl MessagePayload {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = AbiReader::new(data);
+        let msg_type = MessageType::try_from(reader.read_u8()?)
+            .map_err(|_| error!(ErrorCode::InvalidMessageType))?;
+        let payload = Self {
+            msg_type,
+            data: reader.read_bytes()?.to_vec(),
+            timestamp: reader.read_u64()?,
+            message_id: reader.read_bytes32()?,
+        };
+        reader.finish()?;
+        Ok(payload)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(u8::from(self.msg_type).into())
+            .push_bytes(&self.data)
+            .push_uint(self.timestamp.into())
+            .push_bytes32(&self.message_id)
+            .finish()
+    }
+}
+
+// Raw payload bytes of a posted VAA, decoded into a `MessagePayload` once the
+// VAA itself has been validated
+#[derive(Clone, Default)]
+pub struct VaaPayload(Vec<u8>);
+
+impl AsRef<[u8]> for VaaPayload {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl VaaPayload {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl AnchorSerialize for VaaPayload {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+impl AnchorDeserialize for VaaPayload {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(VaaPayload(bytes))
+    }
+}
+
+// abi.encode(uint256 requestId, bool verified)
+pub struct VerificationResponse {
+    pub request_id: u64,
+    pub verified: bool,
+}
+
+impl VerificationResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(self.request_id.into())
+            .push_bool(self.verified)
+            .finish()
+    }
+}
+
+// abi.encode(uint256 transferId, bool success, uint256 dust)
+pub struct TokenTransferResponse {
+    pub transfer_id: u64,
+    pub success: bool,
+    // Part of the amount below the mint's precision, which was not released
+    // here and is refunded to the sender on the source chain
+    pub dust: [u8; 32],
+}
+
+impl TokenTransferResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(self.transfer_id.into())
+            .push_bool(self.success)
+            .push_bytes32(&self.dust)
+            .finish()
+    }
+}
+
+// abi.encode(uint256 requestId, bool verified)
+pub struct CredentialVerificationResponse {
+    pub request_id: u64,
+    pub verified: bool,
+}
+
+impl CredentialVerificationResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(self.request_id.into())
+            .push_bool(self.verified)
+            .finish()
+    }
+}
+
+// abi.encode(uint256 requestId, bool success)
+pub struct RoleSyncResponse {
+    pub request_id: u64,
+    pub success: bool,
+}
+
+impl RoleSyncResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(self.request_id.into())
+            .push_bool(self.success)
+            .finish()
+    }
+}
+
+// abi.encode(uint256 requestId, bool resolved, bytes didDocument), where
+// didDocument is the borsh-serialized `DidDocument` (empty if unresolved)
+pub struct DIDResolutionResponse {
+    pub request_id: u64,
+    pub resolved: bool,
+    pub did_document: Vec<u8>,
+}
+
+impl DIDResolutionResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .push_uint(self.request_id.into())
+            .push_bool(self.resolved)
+            .push_bytes(&self.did_document)
+            .finish()
+    }
+}
+
+#[event]
+pub struct MessageReceivedEvent {
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    pub message_id: [u8; 32],
+    pub vaa_hash: [u8; 32],
+}
+
+#[event]
+pub struct VerificationEvent {
+    pub request_id: u64,
+    pub did: String,
+    pub verified: bool,
+}
+
+#[event]
+pub struct IdentityTokenIssuedEvent {
+    pub did_hash: [u8; 32],
+    pub holder: Pubkey,
+    pub mint: Pubkey,
+    pub sbt_token_id: u64,
+}
+
+#[event]
+pub struct AssetCreationEvent {
+    pub issuer: Pubkey,
+    pub name: String,
+    pub symbol: String,
+}
+
+#[event]
+pub struct CredentialVerificationEvent {
+    pub request_id: u64,
+    pub credential_hash: [u8; 32],
+    pub verified: bool,
+}
+
+#[event]
+pub struct RoleSyncEvent {
+    pub request_id: u64,
+    pub role: [u8; 32],
+    pub account: Pubkey,
+    pub is_grant: bool,
+    pub source_chain: u16,
+    pub foreign_address: [u8; 20],
+}
+
+#[event]
+pub struct AddressLinkedEvent {
+    pub chain_id: u16,
+    pub foreign_address: [u8; 20],
+    pub account: Pubkey,
+    pub verified: bool,
+}
+
+#[event]
+pub struct AddressUnlinkedEvent {
+    pub chain_id: u16,
+    pub foreign_address: [u8; 20],
+    pub account: Pubkey,
+}
+
+#[event]
+pub struct AssetRegisteredEvent {
+    pub chain_id: u16,
+    pub token_address: [u8; 20],
+    pub mint: Pubkey,
+    pub foreign_decimals: u8,
+    pub decimals: u8,
+}
+
+#[event]
+pub struct TokenTransferReceivedEvent {
+    pub transfer_id: u64,
+    pub source_chain: u16,
+    pub token_address: [u8; 20],
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub dust: [u8; 32],
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct TokenTransferInitiatedEvent {
+    pub transfer_id: u64,
+    pub token_address: [u8; 20],
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub sender: Pubkey,
+    pub recipient: [u8; 20],
+}
+
+// Local role change, laid out like `RoleSyncEvent` so both can be indexed
+// the same way. `request_id` is 0 unless the change was sent to other chains
+#[event]
+pub struct RoleUpdatedEvent {
+    pub request_id: u64,
+    pub role: [u8; 32],
+    pub account: Pubkey,
+    pub is_grant: bool,
+    pub sender: Pubkey,
+}
+
+#[event]
+pub struct RoleAdminChangedEvent {
+    pub role: [u8; 32],
+    pub previous_admin_role: [u8; 32],
+    pub new_admin_role: [u8; 32],
+}
+
+#[event]
+pub struct DIDResolutionEvent {
+    pub request_id: u64,
+    pub did: String,
+    pub resolved: bool,
+}
+
+#[event]
+pub struct CredentialStoredEvent {
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+    pub schema: Pubkey,
+    pub credential_type: CredentialType,
+    pub issuer: Pubkey,
+    pub subject: Pubkey,
+}
+
+#[event]
+pub struct CredentialRevokedEvent {
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+    pub issuer: Pubkey,
+    pub subject: Pubkey,
+    pub revoked_by: Pubkey,
+    pub revocation_date: u64,
+}
+
+#[event]
+pub struct CredentialRevokerChangedEvent {
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+    pub revoker: Option<Pubkey>,
+}
+
+#[event]
+pub struct CredentialPresentedEvent {
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+    pub issuer: Pubkey,
+    pub subject: Pubkey,
+}
+
+#[event]
+pub struct CredentialStatusChangedEvent {
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+    pub status: CredentialStatus,
+}
+
+#[event]
+pub struct CredentialMigratedEvent {
+    pub legacy_pubkey: Pubkey,
+    pub credential_pubkey: Pubkey,
+    pub credential_hash: [u8; 32],
+}
+
+#[event]
+pub struct DidRegisteredEvent {
+    pub did_record: Pubkey,
+    pub did: String,
+    pub controller: Pubkey,
+}
+
+#[event]
+pub struct DidUpdatedEvent {
+    pub did_record: Pubkey,
+    pub did_hash: [u8; 32],
+}
+
+#[event]
+pub struct DidDeactivatedEvent {
+    pub did_record: Pubkey,
+    pub did_hash: [u8; 32],
+}
+
+#[event]
+pub struct DidControllerAddedEvent {
+    pub did_record: Pubkey,
+    pub did: String,
+    pub controller: Pubkey,
+    pub added_by: Pubkey,
+}
+
+#[event]
+pub struct DidControllerRemovedEvent {
+    pub did_record: Pubkey,
+    pub did: String,
+    pub controller: Pubkey,
+    pub removed_by: Pubkey,
+}
+
+#[event]
+pub struct SchemaRegisteredEvent {
+    pub schema_pubkey: Pubkey,
+    pub schema_id: u32,
+    pub version: u16,
+    pub credential_type: CredentialType,
+    pub json_schema_hash: [u8; 32],
+}
+
+#[event]
+pub struct TrustedIssuerAddedEvent {
+    pub issuer: Pubkey,
+    pub credential_types: Vec<CredentialType>,
+}
+
+#[event]
+pub struct TrustedIssuerStatusChangedEvent {
+    pub issuer: Pubkey,
+    pub suspended: bool,
+}
+
+#[event]
+pub struct EmitterRegisteredEvent {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+#[event]
+pub struct EmitterUpdatedEvent {
+    pub chain: u16,
+    pub previous_address: [u8; 32],
+    pub address: [u8; 32],
+}
+
+#[event]
+pub struct EmitterRemovedEvent {
+    pub chain: u16,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid chain ID")]
+    InvalidChain,
+    #[msg("Invalid message type")]
+    InvalidMessageType,
+    #[msg("String too long")]
+    StringTooLong,
+    #[msg("Unauthorized action")]
+    Unauthorized,
+    #[msg("Posted VAA payload does not match its declared size")]
+    InvalidVaaPayload,
+    #[msg("Emitter address must not be zero")]
+    InvalidEmitterAddress,
+    #[msg("VAA emitter is not registered for its source chain")]
+    UnregisteredEmitter,
+    #[msg("Payload is not a canonical ABI encoding")]
+    MalformedPayload,
+    #[msg("ABI integer does not fit the target type")]
+    AbiValueOutOfRange,
+    #[msg("Payload is shorter than its encoding requires")]
+    PayloadTooShort,
+    #[msg("Credential account does not match the requested hash")]
+    InvalidCredentialAccount,
+    #[msg("Credential expiration must be in the future")]
+    InvalidExpiration,
+    #[msg("Credential status does not allow this change")]
+    InvalidCredentialStatus,
+    #[msg("Issuer has been suspended")]
+    IssuerSuspended,
+    #[msg("Issuer is not trusted for this credential type")]
+    IssuerNotAuthorized,
+    #[msg("Trusted issuer must list between one and four credential types")]
+    InvalidCredentialTypes,
+    #[msg("Trusted issuer account does not match the credential issuer")]
+    InvalidIssuerAccount,
+    #[msg("Schema lists too many required claim fields")]
+    TooManyClaimFields,
+    #[msg("DID must use the did: scheme and match the registered document")]
+    InvalidDid,
+    #[msg("DID has been deactivated")]
+    DidDeactivated,
+    #[msg("DID document exceeds the on-chain entry limits")]
+    TooManyDidEntries,
+    #[msg("Verification method key does not match its type")]
+    InvalidVerificationMethod,
+    #[msg("DID record account does not match the requested DID")]
+    InvalidDidAccount,
+    #[msg("Controller is already present, missing, or the last one left")]
+    InvalidDidController,
+    #[msg("Identity token accounts are missing or do not match the DID")]
+    InvalidIdentityTokenAccount,
+    #[msg("Verification record account is missing, mismatched or already used")]
+    InvalidVerificationRecord,
+    #[msg("Role account does not match the role and account")]
+    InvalidRoleAccount,
+    #[msg("Signer does not hold the required role")]
+    MissingRole,
+    #[msg("Address link does not match the foreign address or account")]
+    InvalidAddressLink,
+    #[msg("Signature was not made by the foreign address")]
+    InvalidLinkSignature,
+    #[msg("Foreign address is already linked to another account")]
+    AddressAlreadyLinked,
+    #[msg("Transfer record account does not match the transfer")]
+    InvalidTransferRecord,
+    #[msg("Transfer has already been processed")]
+    TransferAlreadyProcessed,
+    #[msg("Custody account does not match the token mint")]
+    InvalidCustodyAccount,
+    #[msg("Transfer amount must be greater than zero")]
+    InvalidTransferAmount,
+    #[msg("Recipient does not match the transfer")]
+    InvalidRecipient,
+    #[msg("Token transfer account was not supplied")]
+    MissingTokenAccount,
+    #[msg("Token is not registered for this chain")]
+    UnregisteredAsset,
+    #[msg("Token mint does not match the registered asset")]
+    AssetMintMismatch,
+    #[msg("Invalid asset registration")]
+    InvalidAssetRegistration,
+    #[msg("Transfer amount does not fit the destination token")]
+    TransferAmountOverflow,
+    #[msg("Transfer amount has dust below the destination token's precision")]
+    TransferAmountHasDust,
+    #[msg("Address link was not signed by the foreign address")]
+    AddressLinkNotVerified,
+}
+
+// Wraps an encoded message in a `MessagePayload`. Like the Solidity side, the
+// message id is a keccak256 over the packed request fields and timestamp. The
+// target chain and the Wormhole sequence the message is posted under are
+// included, so that ids are unique even for equal request ids sent to
+// different chains within the same second.
+fn response_payload(
+    msg_type: MessageType,
+    id: u64,
+    target_chain: u16,
+    sequence: u64,
+    data: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let message_id = keccak::hashv(&[
+        &[u8::from(msg_type)],
+        &abi::uint_word(id.into()),
+        &abi::uint_word(target_chain.into()),
+        &abi::uint_word(sequence.into()),
+        &abi::uint_word(timestamp.into()),
+    ])
+    .to_bytes();
+    Ok(MessagePayload {
+        msg_type,
+        data,
+        timestamp,
+        message_id,
+    }
+    .encode())
+}
+
+impl<'info> ReceiveMessage<'info> {
+    // Pay out an inbound transfer. Wrapped tokens, whose mint authority is
+    // the program's token authority, are minted; tokens native to Solana are
+    // released from the custody account they were locked in by `bridge_out`
+    fn release_tokens(&self, amount: u64, authority_bump: Option<u8>) -> Result<()> {
+        let missing = || error!(ErrorCode::MissingTokenAccount);
+        let token_mint = self.token_mint.as_ref().ok_or_else(missing)?;
+        let recipient = self.recipient.as_ref().ok_or_else(missing)?;
+        let token_program = self.token_program.as_ref().ok_or_else(missing)?;
+        let token_authority = self.token_authority.as_ref().ok_or_else(missing)?;
+        let authority_bump = authority_bump.ok_or_else(missing)?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[TransferRecord::AUTHORITY_SEED, &[authority_bump]]];
+        if token_mint.mint_authority == COption::Some(token_authority.key()) {
+            return anchor_spl::token::mint_to(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    anchor_spl::token::MintTo {
+                        mint: token_mint.to_account_info(),
+                        to: recipient.to_account_info(),
+                        authority: token_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            );
+        }
+
+        let custody = self
+            .custody
+            .as_ref()
+            .ok_or_else(|| error!(ErrorCode::InvalidCustodyAccount))?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[TransferRecord::CUSTODY_SEED_PREFIX, token_mint.key().as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(custody.key(), expected, ErrorCode::InvalidCustodyAccount);
+        anchor_spl::token::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                anchor_spl::token::TransferChecked {
+                    from: custody.to_account_info(),
+                    mint: token_mint.to_account_info(),
+                    to: recipient.to_account_info(),
+                    authority: token_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            token_mint.decimals,
+        )
+    }
+
+    // Record an inbound transfer. Each transfer id is paid out once per
+    // source chain, even if it is carried by more than one VAA
+    fn record_transfer(&self, record: TransferRecord) -> Result<()> {
+        let account = self
+            .transfer_record
+            .as_ref()
+            .ok_or_else(|| error!(ErrorCode::InvalidTransferRecord))?;
+        let chain = record.source_chain.to_le_bytes();
+        let transfer_id = record.transfer_id.to_le_bytes();
+        let (expected, bump) =
+            Pubkey::find_program_address(&[TransferRecord::SEED_PREFIX, &chain, &transfer_id], &crate::ID);
+        require_keys_eq!(account.key(), expected, ErrorCode::InvalidTransferRecord);
+        require!(
+            account.owner != &crate::ID,
+            ErrorCode::TransferAlreadyProcessed
+        );
+
+        let info = account.to_account_info();
+        create_pda_account(
+            self.authority.to_account_info(),
+            info.clone(),
+            self.system_program.to_account_info(),
+            TransferRecord::SPACE,
+            self.wormhole.rent.minimum_balance(TransferRecord::SPACE),
+            &crate::ID,
+            &[TransferRecord::SEED_PREFIX, &chain, &transfer_id, &[bump]],
+        )?;
+        record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+
+    // Mirror a role grant or revocation from another chain by creating or
+    // closing the matching assignment. Repeating a sync is a no-op
+    fn apply_role_sync(&self, role: [u8; 32], account: Pubkey, is_grant: bool, source_chain: u16) -> Result<()> {
+        let role_assignment = self
+            .role_assignment
+            .as_ref()
+            .ok_or_else(|| error!(ErrorCode::InvalidRoleAccount))?;
+        let (expected, bump) =
+            Pubkey::find_program_address(&[RoleAssignment::SEED_PREFIX, &role, account.as_ref()], &crate::ID);
+        require_keys_eq!(role_assignment.key(), expected, ErrorCode::InvalidRoleAccount);
+
+        let info = role_assignment.to_account_info();
+        let assigned = role_assignment.owner == &crate::ID && !role_assignment.data_is_empty();
+        if is_grant && !assigned {
+            create_pda_account(
+                self.authority.to_account_info(),
+                info.clone(),
+                self.system_program.to_account_info(),
+                RoleAssignment::SPACE,
+                self.wormhole.rent.minimum_balance(RoleAssignment::SPACE),
+                &crate::ID,
+                &[RoleAssignment::SEED_PREFIX, &role, account.as_ref(), &[bump]],
+            )?;
+            let assignment = RoleAssignment {
+                role,
+                account,
+                source_chain,
+                granted_at: Clock::get()?.unix_timestamp as u64,
+            };
+            assignment.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        } else if !is_grant && assigned {
+            close_account(info, self.authority.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // Persist the outcome of a verification request so it can be queried
+    // on-chain by request id
+    fn record_verification(&self, record: VerificationRecord) -> Result<()> {
+        let account = self
+            .verification_record
+            .as_ref()
+            .ok_or_else(|| error!(ErrorCode::InvalidVerificationRecord))?;
+        let chain = record.source_chain.to_le_bytes();
+        let request_id = record.request_id.to_le_bytes();
+        let (expected, bump) =
+            Pubkey::find_program_address(&[VerificationRecord::SEED_PREFIX, &chain, &request_id], &crate::ID);
+        require_keys_eq!(account.key(), expected, ErrorCode::InvalidVerificationRecord);
+        require!(
+            account.owner != &crate::ID,
+            ErrorCode::InvalidVerificationRecord
+        );
+
+        let info = account.to_account_info();
+        create_pda_account(
+            self.authority.to_account_info(),
+            info.clone(),
+            self.system_program.to_account_info(),
+            VerificationRecord::SPACE,
+            self.wormhole.rent.minimum_balance(VerificationRecord::SPACE),
+            &crate::ID,
+            &[VerificationRecord::SEED_PREFIX, &chain, &request_id, &[bump]],
+        )?;
+        record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+
+    // Mint the soulbound identity token for a verified DID to its primary
+    // controller. The mint is a Token-2022 NonTransferable mint whose on-mint
+    // metadata names the DID and the credential it was verified with; its
+    // mint authority is dropped after the single token is minted. A DID that
+    // already has a token keeps it.
+    fn issue_identity_token(
+        &self,
+        did_record: &DidRecord,
+        sbt_token_id: u64,
+        credential_hash: [u8; 32],
+        source_chain: u16,
+    ) -> Result<()> {
+        use anchor_spl::token_2022::spl_token_2022::{self, extension::ExtensionType, instruction::AuthorityType};
+        use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+        use anchor_spl::token_interface::spl_token_metadata_interface::state::{Field, TokenMetadata};
+
+        let missing = || error!(ErrorCode::InvalidIdentityTokenAccount);
+        let mint = self.identity_mint.as_ref().ok_or_else(missing)?;
+        let identity_token = self.identity_token.as_ref().ok_or_else(missing)?;
+        let holder = self.identity_holder.as_ref().ok_or_else(missing)?;
+        let holder_token_account = self.identity_holder_token_account.as_ref().ok_or_else(missing)?;
+        let token_program = self.token_2022_program.as_ref().ok_or_else(missing)?;
+        let associated_token_program = self.associated_token_program.as_ref().ok_or_else(missing)?;
+
+        let did_hash = did_record.did_hash;
+        let (mint_key, mint_bump) =
+            Pubkey::find_program_address(&[IdentityToken::MINT_SEED_PREFIX, &did_hash], &crate::ID);
+        require_keys_eq!(mint.key(), mint_key, ErrorCode::InvalidIdentityTokenAccount);
+        let (token_key, token_bump) =
+            Pubkey::find_program_address(&[IdentityToken::SEED_PREFIX, &did_hash], &crate::ID);
+        require_keys_eq!(identity_token.key(), token_key, ErrorCode::InvalidIdentityTokenAccount);
+        let holder_key = did_record.document.controllers[0];
+        require_keys_eq!(holder.key(), holder_key, ErrorCode::InvalidIdentityTokenAccount);
+        require_keys_eq!(
+            holder_token_account.key(),
+            anchor_spl::associated_token::get_associated_token_address_with_program_id(
+                &holder_key,
+                &mint_key,
+                &spl_token_2022::ID
+            ),
+            ErrorCode::InvalidIdentityTokenAccount
+        );
+
+        if !identity_token.data_is_empty() {
+            return Ok(());
+        }
+
+        let mint_seeds: &[&[u8]] = &[IdentityToken::MINT_SEED_PREFIX, &did_hash, &[mint_bump]];
+        let signer = &[mint_seeds];
+        let mint_info = mint.to_account_info();
+        let token_program_info = token_program.to_account_info();
+
+        // The mint is created at its final size so the metadata writes below
+        // only need to realloc, not top up rent
+        let fields = [
+            ("did", did_record.document.did.clone()),
+            ("credential_hash", to_hex(&credential_hash)),
+            ("sbt_token_id", sbt_token_id.to_string()),
+        ];
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(mint_key),
+            mint: mint_key,
+            name: IdentityToken::NAME.to_string(),
+            symbol: IdentityToken::SYMBOL.to_string(),
+            uri: did_record
+                .document
+                .document_cid
+                .as_ref()
+                .map(|cid| format!("ipfs://{cid}"))
+                .unwrap_or_default(),
+            additional_metadata: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        };
+        let mint_space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::NonTransferable,
+            ExtensionType::MetadataPointer,
+        ])?;
+        let mint_lamports = self.wormhole.rent.minimum_balance(mint_space + metadata.tlv_size_of()?);
+        create_pda_account(
+            self.authority.to_account_info(),
+            mint_info.clone(),
+            self.system_program.to_account_info(),
+            mint_space,
+            mint_lamports,
+            &spl_token_2022::ID,
+            mint_seeds,
+        )?;
+
+        anchor_spl::token_interface::non_transferable_mint_initialize(CpiContext::new(
+            token_program_info.clone(),
+            anchor_spl::token_interface::NonTransferableMintInitialize {
+                token_program_id: token_program_info.clone(),
+                mint: mint_info.clone(),
+            },
+        ))?;
+        anchor_spl::token_interface::metadata_pointer_initialize(
+            CpiContext::new(
+                token_program_info.clone(),
+                anchor_spl::token_interface::MetadataPointerInitialize {
+                    token_program_id: token_program_info.clone(),
+                    mint: mint_info.clone(),
+                },
+            ),
+            Some(mint_key),
+            Some(mint_key),
+        )?;
+        anchor_spl::token_2022::initialize_mint2(
+            CpiContext::new(
+                token_program_info.clone(),
+                anchor_spl::token_2022::InitializeMint2 { mint: mint_info.clone() },
+            ),
+            0,
+            &mint_key,
+            None,
+        )?;
+        anchor_spl::token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program_info.clone(),
+                anchor_spl::token_interface::TokenMetadataInitialize {
+                    token_program_id: token_program_info.clone(),
+                    metadata: mint_info.clone(),
+                    update_authority: mint_info.clone(),
+                    mint_authority: mint_info.clone(),
+                    mint: mint_info.clone(),
+                },
+                signer,
+            ),
+            metadata.name.clone(),
+            metadata.symbol.clone(),
+            metadata.uri.clone(),
+        )?;
+        for (key, value) in fields {
+            anchor_spl::token_interface::token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    token_program_info.clone(),
+                    anchor_spl::token_interface::TokenMetadataUpdateField {
+                        token_program_id: token_program_info.clone(),
+                        metadata: mint_info.clone(),
+                        update_authority: mint_info.clone(),
+                    },
+                    signer,
+                ),
+                Field::Key(key.to_string()),
+                value,
+            )?;
+        }
+
+        anchor_spl::associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: self.authority.to_account_info(),
+                associated_token: holder_token_account.to_account_info(),
+                authority: holder.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: token_program_info.clone(),
+            },
+        ))?;
+        anchor_spl::token_2022::mint_to(
+            CpiContext::new_with_signer(
+                token_program_info.clone(),
+                anchor_spl::token_2022::MintTo {
+                    mint: mint_info.clone(),
+                    to: holder_token_account.to_account_info(),
+                    authority: mint_info.clone(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+        anchor_spl::token_2022::set_authority(
+            CpiContext::new_with_signer(
+                token_program_info,
+                anchor_spl::token_2022::SetAuthority {
+                    current_authority: mint_info.clone(),
+                    account_or_mint: mint_info,
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let token_info = identity_token.to_account_info();
+        create_pda_account(
+            self.authority.to_account_info(),
+            token_info.clone(),
+            self.system_program.to_account_info(),
+            IdentityToken::SPACE,
+            self.wormhole.rent.minimum_balance(IdentityToken::SPACE),
+            &crate::ID,
+            &[IdentityToken::SEED_PREFIX, &did_hash, &[token_bump]],
+        )?;
+        let record = IdentityToken {
+            did_hash,
+            holder: holder_key,
+            mint: mint_key,
+            source_chain,
+            sbt_token_id,
+            credential_hash,
+            issued_at: Clock::get()?.unix_timestamp as u64,
+        };
+        record.try_serialize(&mut &mut token_info.try_borrow_mut_data()?[..])?;
+
+        emit!(IdentityTokenIssuedEvent {
+            did_hash,
+            holder: holder_key,
+            mint: mint_key,
+            sbt_token_id,
+        });
+
+        Ok(())
+    }
+
+    // Publish a response through the core bridge, paid for by the relayer
+    fn post_response(&mut self, payload: Vec<u8>, bumps: &ReceiveMessageBumps) -> Result<()> {
+        self.wormhole.post(
+            self.authority.to_account_info(),
+            self.system_program.to_account_info(),
+            payload,
+            &bumps.wormhole,
+        )
+    }
+}
+
+impl<'info> PostWormholeMessage<'info> {
+    // Publish a message through the core bridge under the next batch nonce
+    fn post(
+        &mut self,
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+        payload: Vec<u8>,
+        bumps: &PostWormholeMessageBumps,
+    ) -> Result<()> {
+        let batch_id = self.wormhole_config.nonce;
+        self.wormhole_config.nonce = batch_id.wrapping_add(1);
+
+        post_message(
+            self.wormhole_program.to_account_info(),
+            wormhole::PostMessage {
+                config: self.wormhole_bridge.to_account_info(),
+                message: self.wormhole_message.to_account_info(),
+                emitter: self.wormhole_emitter.to_account_info(),
+                sequence: self.wormhole_sequence.to_account_info(),
+                payer,
+                fee_collector: self.wormhole_fee_collector.to_account_info(),
+                clock: self.clock.to_account_info(),
+                rent: self.rent.to_account_info(),
+                system_program,
+            },
+            self.wormhole_bridge.fee(),
+            self.wormhole_emitter.bump,
+            bumps.wormhole_message,
+            batch_id,
+            payload,
+        )
+    }
+}
+
+// Registry entry for `token_address` on `chain_id`, which must be bridged to
+// `mint`
+fn registered_asset<'a>(
+    wrapped_asset: Option<&'a WrappedAsset>,
+    chain_id: u16,
+    token_address: &[u8; 20],
+    mint: &Pubkey,
+) -> Result<&'a WrappedAsset> {
+    let wrapped_asset = wrapped_asset.ok_or_else(|| error!(ErrorCode::UnregisteredAsset))?;
+    require!(
+        wrapped_asset.chain_id == chain_id && wrapped_asset.token_address == *token_address,
+        ErrorCode::UnregisteredAsset
+    );
+    require_keys_eq!(wrapped_asset.mint, *mint, ErrorCode::AssetMintMismatch);
+    Ok(wrapped_asset)
+}
+
+// Solana account linked to `foreign_address` on `chain_id`. Messages naming an
+// unlinked address fail, so they can be processed again once it is linked.
+// Roles follow only links both keys signed: a credential manager's link is
+// not proof that the account speaks for the foreign address
+pub fn linked_account(address_link: Option<&AddressLink>, chain_id: u16, foreign_address: &[u8; 20]) -> Result<Pubkey> {
+    let address_link = address_link.ok_or_else(|| error!(ErrorCode::InvalidAddressLink))?;
+    require!(
+        address_link.chain_id == chain_id && address_link.foreign_address == *foreign_address,
+        ErrorCode::InvalidAddressLink
+    );
+    require!(address_link.verified, ErrorCode::AddressLinkNotVerified);
+    Ok(address_link.account)
+}
+
+// Credential stored under the requested hash, if it is active and its issuer
+// is trusted for its type. The supplied account must be the address derived
+// from the hash; a credential that was never stored there, or whose issuer is
+// no longer trusted for it, yields `None` so the request is reported as
+// unverified rather than failing the message.
+fn trusted_credential(
+    account: Option<&UncheckedAccount>,
+    trusted_issuer: Option<&UncheckedAccount>,
+    credential_hash: &[u8; 32],
+) -> Result<Option<Credential>> {
+    let account = account.ok_or_else(|| error!(ErrorCode::InvalidCredentialAccount))?;
+    let (expected, _) = Pubkey::find_program_address(&[Credential::SEED_PREFIX, credential_hash], &crate::ID);
+    require_keys_eq!(account.key(), expected, ErrorCode::InvalidCredentialAccount);
+
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+    let credential = Credential::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    let now = Clock::get()?.unix_timestamp as u64;
+    if credential.hash != *credential_hash || !credential.is_active(now) {
+        return Ok(None);
+    }
+
+    let trusted_issuer = trusted_issuer.ok_or_else(|| error!(ErrorCode::InvalidIssuerAccount))?;
+    let (expected, _) =
+        Pubkey::find_program_address(&[TrustedIssuer::SEED_PREFIX, credential.issuer.as_ref()], &crate::ID);
+    require_keys_eq!(trusted_issuer.key(), expected, ErrorCode::InvalidIssuerAccount);
+
+    if trusted_issuer.owner != &crate::ID || trusted_issuer.data_is_empty() {
+        return Ok(None);
+    }
+    let trusted_issuer = TrustedIssuer::try_deserialize(&mut &trusted_issuer.try_borrow_data()?[..])?;
+    Ok(trusted_issuer.allows(credential.credential_type).then_some(credential))
+}
+
+// Seed for a DID's record: the keccak hash of the full DID string
+pub fn did_hash(did: &str) -> [u8; 32] {
+    keccak::hash(did.as_bytes()).to_bytes()
+}
+
+// Key named by a `did:sol:<network>:<key>` identifier, or `None` for DIDs of
+// other methods. The key must be in canonical base58
+pub fn sol_did_key(did: &str) -> Result<Option<Pubkey>> {
+    use std::str::FromStr;
+
+    let Some(rest) = did.strip_prefix("did:sol:") else {
+        return Ok(None);
+    };
+    let encoded = rest.rsplit(':').next().unwrap_or_default();
+    let key = Pubkey::from_str(encoded).map_err(|_| error!(ErrorCode::InvalidDid))?;
+    require!(key.to_string() == encoded, ErrorCode::InvalidDid);
+    Ok(Some(key))
+}
+
+// Text an EVM address signs to link itself to `account`. It names the
+// program and chain so the signature cannot be reused for another deployment
+pub fn link_message(chain_id: u16, foreign_address: &[u8; 20], account: &Pubkey) -> String {
+    format!(
+        "Link 0x{} on Wormhole chain {} to Solana account {} for program {}",
+        to_hex(foreign_address),
+        chain_id,
+        account,
+        crate::ID
+    )
+}
+
+// Hash signed by `personal_sign` (EIP-191) for `message`
+pub fn eth_message_hash(message: &str) -> [u8; 32] {
+    keccak::hashv(&[
+        b"\x19Ethereum Signed Message:\n",
+        message.len().to_string().as_bytes(),
+        message.as_bytes(),
+    ])
+    .to_bytes()
+}
+
+// EVM address that produced `signature` (r || s || v) over `hash`. Like
+// OpenZeppelin's ECDSA.recover, v may be 27/28 or 0/1 and high-s signatures
+// are rejected so that each signature has a single valid encoding
+pub fn recover_eth_address(hash: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20]> {
+    // secp256k1 group order divided by two
+    const HALF_ORDER: [u8; 32] = [
+        0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+    ];
+    require!(
+        signature[32..64] <= HALF_ORDER[..],
+        ErrorCode::InvalidLinkSignature
+    );
+    let recovery_id = match signature[64] {
+        0 | 27 => 0,
+        1 | 28 => 1,
+        _ => return Err(ErrorCode::InvalidLinkSignature.into()),
+    };
+    let public_key = secp256k1_recover(hash, recovery_id, &signature[..64])
+        .map_err(|_| error!(ErrorCode::InvalidLinkSignature))?;
+
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&keccak::hash(&public_key.to_bytes()).to_bytes()[12..]);
+    Ok(address)
+}
+
+// Serialized document for a DID resolution request, or `None` if the DID is
+// not registered or has been deactivated
+fn resolve_did(account: Option<&UncheckedAccount>, did: &[u8]) -> Result<Option<Vec<u8>>> {
+    Ok(load_did_record(account, did)?
+        .map(|did_record| borsh::to_vec(&did_record.document))
+        .transpose()?)
+}
+
+// Active record for `did`, or `None` if the DID is not registered or has
+// been deactivated. As with credentials, the supplied account must be the
+// address derived from the requested DID.
+fn load_did_record(account: Option<&UncheckedAccount>, did: &[u8]) -> Result<Option<DidRecord>> {
+    let account = account.ok_or_else(|| error!(ErrorCode::InvalidDidAccount))?;
+    let hash = keccak::hash(did).to_bytes();
+    let (expected, _) = Pubkey::find_program_address(&[DidRecord::SEED_PREFIX, &hash], &crate::ID);
+    require_keys_eq!(account.key(), expected, ErrorCode::InvalidDidAccount);
+
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+    let did_record = DidRecord::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    if did_record.deactivated {
+        return Ok(None);
+    }
+    Ok(Some(did_record))
+}
+
+// Gate for privileged instructions: the signer must hold `role` through the
+// supplied assignment
+fn require_role(role_assignment: Option<&RoleAssignment>, role: &[u8; 32], signer: &Pubkey) -> Result<()> {
+    let assignment = role_assignment.ok_or_else(|| error!(ErrorCode::MissingRole))?;
+    require!(
+        assignment.role == *role && assignment.account == *signer,
+        ErrorCode::MissingRole
+    );
+    Ok(())
+}
+
+// Admin role of `role`, read from its `RoleAdmin` account if one exists
+pub fn role_admin(account: &AccountInfo, role: &[u8; 32]) -> Result<[u8; 32]> {
+    let (expected, _) = Pubkey::find_program_address(&[RoleAdmin::SEED_PREFIX, role], &crate::ID);
+    require_keys_eq!(account.key(), expected, ErrorCode::InvalidRoleAccount);
+
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(roles::DEFAULT_ADMIN_ROLE);
+    }
+    Ok(RoleAdmin::try_deserialize(&mut &account.try_borrow_data()?[..])?.admin_role)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Create a PDA owned by `owner`, the way `init` does: accounts that were
+// pre-funded by someone else are topped up, allocated and assigned instead
+fn create_pda_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    space: usize,
+    lamports: u64,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    use anchor_lang::system_program::{allocate, assign, create_account, transfer};
+    use anchor_lang::system_program::{Allocate, Assign, CreateAccount, Transfer};
+
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        return create_account(
+            CpiContext::new_with_signer(
+                system_program,
+                CreateAccount { from: payer, to: account },
+                &[seeds],
+            ),
+            lamports,
+            space as u64,
+            owner,
+        );
+    }
+
+    if current_lamports < lamports {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer { from: payer, to: account.clone() },
+            ),
+            lamports - current_lamports,
+        )?;
+    }
+    allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate { account_to_allocate: account.clone() },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    assign(
+        CpiContext::new_with_signer(
+            system_program,
+            Assign { account_to_assign: account },
+            &[seeds],
+        ),
+        owner,
+    )
+}
+
+// Close a program-owned account that is not wrapped in `Account`, returning
+// its rent to `destination`
+fn close_account<'info>(info: AccountInfo<'info>, destination: AccountInfo<'info>) -> Result<()> {
+    let lamports = info.lamports();
+    **destination.try_borrow_mut_lamports()? += lamports;
+    **info.try_borrow_mut_lamports()? = 0;
+    info.assign(&anchor_lang::system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+// Pay the core bridge fee out of the payer, then post `payload` with the
+// emitter and the message account for its current sequence signing
+fn post_message<'info>(
+    wormhole_program: AccountInfo<'info>,
+    accounts: wormhole::PostMessage<'info>,
+    fee: u64,
+    emitter_bump: u8,
+    message_bump: u8,
+    batch_id: u32,
+    payload: Vec<u8>,
+) -> Result<()> {
+    let sequence = current_sequence(&accounts.sequence).to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[wormhole::SEED_PREFIX_EMITTER, &[emitter_bump]],
+        &[wormhole_constants::SEED_PREFIX_SENT, &sequence, &[message_bump]],
+    ];
+    if fee > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: accounts.payer.clone(),
+                    to: accounts.fee_collector.clone(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    wormhole::post_message(
+        CpiContext::new_with_signer(wormhole_program, accounts, signer_seeds),
+        batch_id,
+        payload,
+        wormhole::Finality::Confirmed,
+    )
+}
+
+// Sequence the core bridge will assign to our emitter's next message. The
+// tracker only exists once the emitter has posted, so it starts at zero.
+fn current_sequence(tracker: &AccountInfo) -> u64 {
+    let data = tracker.try_borrow_data();
+    match data {
+        Ok(data) if !data.is_empty() => wormhole::SequenceTracker::try_deserialize(&mut &data[..])
+            .map(|tracker| tracker.value())
+            .unwrap_or_default(),
+        _ => 0,
     }
 }
 
@@ -0,0 +1,77 @@
+//! Decoders for the inner `data` of each incoming message type.
+//!
+//! Each function mirrors the `abi.encode(...)` call that produces the payload
+//! in CrossChainBridge.sol and rejects anything that is not exactly that
+//! encoding, so malformed input surfaces as an `ErrorCode` rather than a panic.
+
+use anchor_lang::prelude::*;
+
+use crate::abi::{self, AbiReader};
+use crate::ErrorCode;
+
+// abi.encode(uint256 requestId, string did, uint256 sbtTokenId, bytes32 credentialHash)
+pub fn deserialize_verification(data: &[u8]) -> Result<(u64, Vec<u8>, u64, [u8; 32])> {
+    let mut reader = AbiReader::new(data);
+    let request_id = reader.read_u64()?;
+    let did = reader.read_bytes()?;
+    require!(did.len() <= 128, ErrorCode::StringTooLong);
+    let sbt_token_id = reader.read_u64()?;
+    let credential_hash = reader.read_bytes32()?;
+    reader.finish()?;
+    Ok((request_id, did.to_vec(), sbt_token_id, credential_hash))
+}
+
+// abi.encode(address issuer, string name, string symbol)
+pub fn deserialize_asset_creation(data: &[u8]) -> Result<(Pubkey, Vec<u8>, Vec<u8>)> {
+    let mut reader = AbiReader::new(data);
+    let issuer = Pubkey::new_from_array(abi::address_word(&reader.read_address()?));
+    let name = reader.read_bytes()?;
+    require!(name.len() <= 32, ErrorCode::StringTooLong);
+    let symbol = reader.read_bytes()?;
+    require!(symbol.len() <= 10, ErrorCode::StringTooLong);
+    reader.finish()?;
+    Ok((issuer, name.to_vec(), symbol.to_vec()))
+}
+
+// abi.encode(uint256 transferId, address token, uint256 amount, bytes32 recipient)
+pub fn deserialize_token_transfer(data: &[u8]) -> Result<(u64, [u8; 20], [u8; 32], Pubkey)> {
+    let mut reader = AbiReader::new(data);
+    let transfer_id = reader.read_u64()?;
+    let token_address = reader.read_address()?;
+    // Full uint256 word, scaled to the mint's decimals by the caller
+    let amount = reader.read_bytes32()?;
+    // Solana wallet that owns the receiving token account
+    let recipient = Pubkey::new_from_array(reader.read_bytes32()?);
+    reader.finish()?;
+    Ok((transfer_id, token_address, amount, recipient))
+}
+
+// abi.encode(uint256 requestId, bytes32 credentialHash)
+pub fn deserialize_credential_verification(data: &[u8]) -> Result<(u64, [u8; 32])> {
+    let mut reader = AbiReader::new(data);
+    let request_id = reader.read_u64()?;
+    let credential_hash = reader.read_bytes32()?;
+    reader.finish()?;
+    Ok((request_id, credential_hash))
+}
+
+// abi.encode(uint256 requestId, bytes32 role, address account, bool isGrant)
+pub fn deserialize_role_sync(data: &[u8]) -> Result<(u64, [u8; 32], [u8; 20], bool)> {
+    let mut reader = AbiReader::new(data);
+    let request_id = reader.read_u64()?;
+    let role = reader.read_bytes32()?;
+    let account = reader.read_address()?;
+    let is_grant = reader.read_bool()?;
+    reader.finish()?;
+    Ok((request_id, role, account, is_grant))
+}
+
+// abi.encode(uint256 requestId, string did)
+pub fn deserialize_did_resolution(data: &[u8]) -> Result<(u64, Vec<u8>)> {
+    let mut reader = AbiReader::new(data);
+    let request_id = reader.read_u64()?;
+    let did = reader.read_bytes()?;
+    require!(did.len() <= 128, ErrorCode::StringTooLong);
+    reader.finish()?;
+    Ok((request_id, did.to_vec()))
+}
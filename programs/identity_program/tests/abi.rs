@@ -0,0 +1,277 @@
+//! Round-trip tests for the ABI codec. The fixtures are the exact bytes that
+//! `abi.encode` produces in CrossChainBridge.sol for the given values.
+
+mod common;
+
+use common::{error, unhex};
+use identity_program::abi::{self, AbiEncoder, AbiReader};
+use identity_program::{ErrorCode, MessagePayload, MessageType, TokenTransferResponse, VerificationResponse};
+
+// abi.encode(uint256(7), "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q", uint256(42), bytes32(0x22..22))
+const VERIFICATION_DATA: &str = "\
+0000000000000000000000000000000000000000000000000000000000000007\
+0000000000000000000000000000000000000000000000000000000000000080\
+000000000000000000000000000000000000000000000000000000000000002a\
+2222222222222222222222222222222222222222222222222222222222222222\
+000000000000000000000000000000000000000000000000000000000000003b\
+6469643a736f6c3a6465766e65743a375971336d45346662435a376250703146\
+366f436b715642475137634e77686858556a44527a634e6e4b38510000000000";
+
+// abi.encode(MessageType.VERIFICATION, VERIFICATION_DATA, uint256(1718000000), bytes32(0x11..11))
+const VERIFICATION_MESSAGE: &str = "\
+0000000000000000000000000000000000000000000000000000000000000000\
+0000000000000000000000000000000000000000000000000000000000000080\
+0000000000000000000000000000000000000000000000000000000066669980\
+1111111111111111111111111111111111111111111111111111111111111111\
+00000000000000000000000000000000000000000000000000000000000000e0\
+0000000000000000000000000000000000000000000000000000000000000007\
+0000000000000000000000000000000000000000000000000000000000000080\
+000000000000000000000000000000000000000000000000000000000000002a\
+2222222222222222222222222222222222222222222222222222222222222222\
+000000000000000000000000000000000000000000000000000000000000003b\
+6469643a736f6c3a6465766e65743a375971336d45346662435a376250703146\
+366f436b715642475137634e77686858556a44527a634e6e4b38510000000000";
+
+// abi.encode(uint256(3), address(0x5FbDB2315678afecb367f032d93F642f64180aa3), uint256(1 ether), bytes32(0x77..77))
+const TOKEN_TRANSFER_DATA: &str = "\
+0000000000000000000000000000000000000000000000000000000000000003\
+0000000000000000000000005fbdb2315678afecb367f032d93f642f64180aa3\
+0000000000000000000000000000000000000000000000000de0b6b3a7640000\
+7777777777777777777777777777777777777777777777777777777777777777";
+
+// abi.encode(MessageType.TOKEN_TRANSFER, TOKEN_TRANSFER_DATA, uint256(1718000100), bytes32(0x22..22))
+const TOKEN_TRANSFER_MESSAGE: &str = "\
+0000000000000000000000000000000000000000000000000000000000000004\
+0000000000000000000000000000000000000000000000000000000000000080\
+00000000000000000000000000000000000000000000000000000000666699e4\
+2222222222222222222222222222222222222222222222222222222222222222\
+0000000000000000000000000000000000000000000000000000000000000080\
+0000000000000000000000000000000000000000000000000000000000000003\
+0000000000000000000000005fbdb2315678afecb367f032d93f642f64180aa3\
+0000000000000000000000000000000000000000000000000de0b6b3a7640000\
+7777777777777777777777777777777777777777777777777777777777777777";
+
+// abi.encode(uint256(11), bytes32(0xcd..cd), address(0x70997970C51812dc3A010C7d01b50e0d17dc79C8), true)
+const ROLE_SYNC_DATA: &str = "\
+000000000000000000000000000000000000000000000000000000000000000b\
+cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd\
+00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8\
+0000000000000000000000000000000000000000000000000000000000000001";
+
+// abi.encode(address(0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266), "Gold Token", "GLD")
+const ASSET_CREATION_DATA: &str = "\
+000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266\
+0000000000000000000000000000000000000000000000000000000000000060\
+00000000000000000000000000000000000000000000000000000000000000a0\
+000000000000000000000000000000000000000000000000000000000000000a\
+476f6c6420546f6b656e00000000000000000000000000000000000000000000\
+0000000000000000000000000000000000000000000000000000000000000003\
+474c440000000000000000000000000000000000000000000000000000000000";
+
+// abi.encode(MessageType.VERIFICATION_RESPONSE, abi.encode(uint256(7), true), uint256(1718000200), bytes32(0x33..33))
+const VERIFICATION_RESPONSE_MESSAGE: &str = "\
+0000000000000000000000000000000000000000000000000000000000000001\
+0000000000000000000000000000000000000000000000000000000000000080\
+0000000000000000000000000000000000000000000000000000000066669a48\
+3333333333333333333333333333333333333333333333333333333333333333\
+0000000000000000000000000000000000000000000000000000000000000040\
+0000000000000000000000000000000000000000000000000000000000000007\
+0000000000000000000000000000000000000000000000000000000000000001";
+
+// abi.encode(uint256(3), true, uint256(999999999999))
+const TOKEN_TRANSFER_RESPONSE_DATA: &str = "\
+0000000000000000000000000000000000000000000000000000000000000003\
+0000000000000000000000000000000000000000000000000000000000000001\
+000000000000000000000000000000000000000000000000000000e8d4a50fff";
+
+#[test]
+fn decodes_verification_message() {
+    let bytes = unhex(VERIFICATION_MESSAGE);
+    let payload = MessagePayload::decode(&bytes).unwrap();
+    assert_eq!(payload.msg_type, MessageType::Verification);
+    assert_eq!(payload.timestamp, 1_718_000_000);
+    assert_eq!(payload.message_id, [0x11; 32]);
+    assert_eq!(payload.data, unhex(VERIFICATION_DATA));
+
+    let mut reader = AbiReader::new(&payload.data);
+    assert_eq!(reader.read_u64().unwrap(), 7);
+    assert_eq!(
+        reader.read_bytes().unwrap(),
+        b"did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q"
+    );
+    assert_eq!(reader.read_u64().unwrap(), 42);
+    assert_eq!(reader.read_bytes32().unwrap(), [0x22; 32]);
+    reader.finish().unwrap();
+
+    assert_eq!(payload.encode(), bytes);
+}
+
+#[test]
+fn decodes_token_transfer_message() {
+    let bytes = unhex(TOKEN_TRANSFER_MESSAGE);
+    let payload = MessagePayload::decode(&bytes).unwrap();
+    assert_eq!(payload.msg_type, MessageType::TokenTransfer);
+    assert_eq!(payload.timestamp, 1_718_000_100);
+
+    let mut reader = AbiReader::new(&payload.data);
+    assert_eq!(reader.read_u64().unwrap(), 3);
+    let token = reader.read_address().unwrap();
+    assert_eq!(token.to_vec(), unhex("5fbdb2315678afecb367f032d93f642f64180aa3"));
+    assert_eq!(reader.read_u128().unwrap(), 1_000_000_000_000_000_000);
+    assert_eq!(reader.read_bytes32().unwrap(), [0x77; 32]);
+    reader.finish().unwrap();
+
+    let encoded = AbiEncoder::new()
+        .push_uint(3)
+        .push_address(&token)
+        .push_uint(1_000_000_000_000_000_000)
+        .push_bytes32(&[0x77; 32])
+        .finish();
+    assert_eq!(encoded, unhex(TOKEN_TRANSFER_DATA));
+    assert_eq!(payload.encode(), bytes);
+}
+
+#[test]
+fn decodes_role_sync_data() {
+    let bytes = unhex(ROLE_SYNC_DATA);
+    let mut reader = AbiReader::new(&bytes);
+    assert_eq!(reader.read_u64().unwrap(), 11);
+    assert_eq!(reader.read_bytes32().unwrap(), [0xcd; 32]);
+    let account = reader.read_address().unwrap();
+    assert!(reader.read_bool().unwrap());
+    reader.finish().unwrap();
+
+    let encoded = AbiEncoder::new()
+        .push_uint(11)
+        .push_bytes32(&[0xcd; 32])
+        .push_address(&account)
+        .push_bool(true)
+        .finish();
+    assert_eq!(encoded, bytes);
+    assert_eq!(abi::address_word(&account).to_vec(), bytes[64..96].to_vec());
+}
+
+#[test]
+fn decodes_asset_creation_data() {
+    let bytes = unhex(ASSET_CREATION_DATA);
+    let mut reader = AbiReader::new(&bytes);
+    let issuer = reader.read_address().unwrap();
+    assert_eq!(reader.read_bytes().unwrap(), b"Gold Token");
+    assert_eq!(reader.read_bytes().unwrap(), b"GLD");
+    reader.finish().unwrap();
+
+    let encoded = AbiEncoder::new()
+        .push_address(&issuer)
+        .push_bytes(b"Gold Token")
+        .push_bytes(b"GLD")
+        .finish();
+    assert_eq!(encoded, bytes);
+}
+
+#[test]
+fn encodes_verification_response() {
+    let payload = MessagePayload {
+        msg_type: MessageType::VerificationResponse,
+        data: VerificationResponse {
+            request_id: 7,
+            verified: true,
+        }
+        .encode(),
+        timestamp: 1_718_000_200,
+        message_id: [0x33; 32],
+    };
+    assert_eq!(payload.encode(), unhex(VERIFICATION_RESPONSE_MESSAGE));
+}
+
+#[test]
+fn encodes_token_transfer_response() {
+    let data = TokenTransferResponse {
+        transfer_id: 3,
+        success: true,
+        dust: abi::uint_word(999_999_999_999),
+    }
+    .encode();
+    assert_eq!(data, unhex(TOKEN_TRANSFER_RESPONSE_DATA));
+}
+
+#[test]
+fn rejects_uint_out_of_range() {
+    let bytes = AbiEncoder::new().push_uint(u64::MAX as u128 + 1).finish();
+    assert_eq!(
+        AbiReader::new(&bytes).read_u64().unwrap_err(),
+        error(ErrorCode::AbiValueOutOfRange)
+    );
+    assert_eq!(AbiReader::new(&bytes).read_u128().unwrap(), u64::MAX as u128 + 1);
+
+    let mut word = [0u8; 32];
+    word[0] = 1;
+    let bytes = AbiEncoder::new().push_bytes32(&word).finish();
+    assert_eq!(
+        AbiReader::new(&bytes).read_u128().unwrap_err(),
+        error(ErrorCode::AbiValueOutOfRange)
+    );
+}
+
+#[test]
+fn rejects_invalid_bool_and_address() {
+    let bytes = AbiEncoder::new().push_uint(2).finish();
+    assert_eq!(
+        AbiReader::new(&bytes).read_bool().unwrap_err(),
+        error(ErrorCode::MalformedPayload)
+    );
+
+    let bytes = AbiEncoder::new().push_bytes32(&[0xff; 32]).finish();
+    assert_eq!(
+        AbiReader::new(&bytes).read_address().unwrap_err(),
+        error(ErrorCode::MalformedPayload)
+    );
+}
+
+#[test]
+fn rejects_unknown_message_type() {
+    let mut bytes = unhex(VERIFICATION_MESSAGE);
+    bytes[31] = 0xff;
+    assert_eq!(
+        MessagePayload::decode(&bytes).unwrap_err(),
+        error(ErrorCode::InvalidMessageType)
+    );
+}
+
+#[test]
+fn rejects_truncated_dynamic_bytes() {
+    let bytes = unhex(VERIFICATION_DATA);
+    let mut reader = AbiReader::new(&bytes[..bytes.len() - 32]);
+    assert_eq!(reader.read_u64().unwrap(), 7);
+    assert_eq!(reader.read_bytes().unwrap_err(), error(ErrorCode::PayloadTooShort));
+}
+
+#[test]
+fn rejects_trailing_bytes() {
+    let mut bytes = unhex(TOKEN_TRANSFER_MESSAGE);
+    bytes.extend_from_slice(&[0u8; 32]);
+    assert_eq!(
+        MessagePayload::decode(&bytes).unwrap_err(),
+        error(ErrorCode::MalformedPayload)
+    );
+}
+
+#[test]
+fn rejects_non_canonical_layout() {
+    // Nonzero padding after the DID string
+    let mut bytes = unhex(VERIFICATION_DATA);
+    let last = bytes.len() - 1;
+    bytes[last] = 1;
+    let mut reader = AbiReader::new(&bytes);
+    reader.read_u64().unwrap();
+    assert_eq!(reader.read_bytes().unwrap_err(), error(ErrorCode::MalformedPayload));
+
+    // Dynamic values stored out of order in the tail
+    let mut bytes = unhex(ASSET_CREATION_DATA);
+    bytes[63] = 0xa0;
+    bytes[95] = 0x60;
+    let mut reader = AbiReader::new(&bytes);
+    reader.read_address().unwrap();
+    assert_eq!(reader.read_bytes().unwrap(), b"GLD");
+    assert_eq!(reader.read_bytes().unwrap(), b"Gold Token");
+    assert_eq!(reader.finish().unwrap_err(), error(ErrorCode::MalformedPayload));
+}
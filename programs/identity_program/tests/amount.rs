@@ -0,0 +1,92 @@
+//! Tests for scaling transfer amounts between 18-decimal ERC-20 tokens and
+//! SPL mints. Word fixtures are big-endian `uint256` values.
+
+mod common;
+
+use common::{error, word};
+use identity_program::abi;
+use identity_program::amount::{to_foreign_amount, to_spl_amount};
+use identity_program::ErrorCode;
+use proptest::prelude::*;
+
+// 1.5 * 10^18
+const ONE_AND_A_HALF_ETHER: &str = "00000000000000000000000000000000000000000000000014d1120d7b160000";
+// 10^18 + 1
+const ONE_ETHER_AND_ONE_WEI: &str = "0000000000000000000000000000000000000000000000000de0b6b3a7640001";
+// 2 * 10^28, which is 2 * 10^19 at 9 decimals
+const TWENTY_BILLION_ETHER: &str = "0000000000000000000000000000000000000000409f9cbc7c4a04c220000000";
+// 10^60, beyond u128
+const TEN_POW_60: &str = "000000000000009f4f2726179a224501d762422c946590d91000000000000000";
+
+#[test]
+fn scales_down_to_spl_decimals() {
+    let no_dust = [0u8; 32];
+    assert_eq!(to_spl_amount(&word(ONE_AND_A_HALF_ETHER), 18, 9).unwrap(), (1_500_000_000, no_dust));
+    assert_eq!(to_spl_amount(&word(ONE_AND_A_HALF_ETHER), 18, 1).unwrap(), (15, no_dust));
+    assert_eq!(to_spl_amount(&abi::uint_word(42), 18, 18).unwrap(), (42, no_dust));
+    assert_eq!(to_spl_amount(&word(TEN_POW_60), 60, 9).unwrap(), (1_000_000_000, no_dust));
+    assert_eq!(to_spl_amount(&[0u8; 32], 18, 9).unwrap(), (0, no_dust));
+}
+
+#[test]
+fn scales_up_from_fewer_decimals() {
+    assert_eq!(to_spl_amount(&abi::uint_word(5), 6, 9).unwrap(), (5_000, [0u8; 32]));
+    assert_eq!(to_foreign_amount(5_000, 9, 6).unwrap(), abi::uint_word(5));
+    assert_eq!(to_foreign_amount(1_500_000_000, 9, 18).unwrap(), word(ONE_AND_A_HALF_ETHER));
+}
+
+#[test]
+fn splits_off_inbound_dust() {
+    assert_eq!(
+        to_spl_amount(&word(ONE_ETHER_AND_ONE_WEI), 18, 9).unwrap(),
+        (1_000_000_000, abi::uint_word(1))
+    );
+    assert_eq!(
+        to_spl_amount(&word(ONE_AND_A_HALF_ETHER), 18, 0).unwrap(),
+        (1, abi::uint_word(500_000_000_000_000_000))
+    );
+    assert_eq!(to_spl_amount(&abi::uint_word(999), 18, 9).unwrap(), (0, abi::uint_word(999)));
+}
+
+#[test]
+fn rejects_outbound_dust() {
+    assert_eq!(
+        to_foreign_amount(5_001, 9, 6).unwrap_err(),
+        error(ErrorCode::TransferAmountHasDust)
+    );
+}
+
+#[test]
+fn rejects_overflow() {
+    assert_eq!(
+        to_spl_amount(&word(TWENTY_BILLION_ETHER), 18, 9).unwrap_err(),
+        error(ErrorCode::TransferAmountOverflow)
+    );
+    assert_eq!(
+        to_spl_amount(&word(TEN_POW_60), 18, 9).unwrap_err(),
+        error(ErrorCode::TransferAmountOverflow)
+    );
+    assert_eq!(
+        to_spl_amount(&abi::uint_word(u64::MAX as u128), 0, 1).unwrap_err(),
+        error(ErrorCode::TransferAmountOverflow)
+    );
+    // u64::MAX at 0 decimals needs 20 digits, and a uint256 holds 77
+    assert!(to_foreign_amount(u64::MAX, 0, 57).is_ok());
+    assert_eq!(
+        to_foreign_amount(u64::MAX, 0, 58).unwrap_err(),
+        error(ErrorCode::TransferAmountOverflow)
+    );
+}
+
+proptest! {
+    #[test]
+    fn round_trips_through_foreign_amount(
+        amount in any::<u64>(),
+        decimals in 0u8..=9,
+        extra in 0u8..=18,
+    ) {
+        let foreign_decimals = decimals + extra;
+        let foreign = to_foreign_amount(amount, decimals, foreign_decimals).unwrap();
+        prop_assert_eq!(to_spl_amount(&foreign, foreign_decimals, decimals).unwrap(), (amount, [0u8; 32]));
+    }
+}
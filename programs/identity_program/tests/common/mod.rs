@@ -0,0 +1,22 @@
+//! Helpers shared by the integration tests. Each test crate uses a subset.
+#![allow(dead_code)]
+
+use anchor_lang::error::Error;
+use identity_program::ErrorCode;
+
+/// Decodes a hex fixture; the input must be well formed.
+pub fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Decodes a 32-byte hex fixture, such as an ABI word.
+pub fn word(s: &str) -> [u8; 32] {
+    unhex(s).try_into().unwrap()
+}
+
+pub fn error(code: ErrorCode) -> Error {
+    code.into()
+}
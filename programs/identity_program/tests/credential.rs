@@ -0,0 +1,73 @@
+//! Tests for the status rules of stored credentials: revocation is final, and
+//! a passed expiry takes precedence over any other stored status.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use identity_program::{Credential, CredentialStatus, CredentialType};
+
+const ISSUED_AT: u64 = 1_718_000_000;
+const EXPIRES_AT: u64 = 1_750_000_000;
+
+fn credential(status: CredentialStatus, expires_at: u64) -> Credential {
+    Credential {
+        hash: [1; 32],
+        schema: Pubkey::new_from_array([2; 32]),
+        credential_type: CredentialType::Kyc,
+        status,
+        issuer: Pubkey::new_from_array([3; 32]),
+        subject: Pubkey::new_from_array([4; 32]),
+        revoker: Some(Pubkey::new_from_array([5; 32])),
+        revocation_date: 0,
+        issued_at: ISSUED_AT,
+        expires_at,
+    }
+}
+
+#[test]
+fn active_until_expiry() {
+    let credential = credential(CredentialStatus::Active, EXPIRES_AT);
+    assert_eq!(credential.effective_status(ISSUED_AT), CredentialStatus::Active);
+    assert_eq!(credential.effective_status(EXPIRES_AT), CredentialStatus::Active);
+    assert!(credential.is_active(EXPIRES_AT));
+
+    assert_eq!(credential.effective_status(EXPIRES_AT + 1), CredentialStatus::Expired);
+    assert!(!credential.is_active(EXPIRES_AT + 1));
+}
+
+#[test]
+fn never_expires_without_expiry() {
+    let credential = credential(CredentialStatus::Active, 0);
+    assert!(!credential.is_expired(u64::MAX));
+    assert_eq!(credential.effective_status(u64::MAX), CredentialStatus::Active);
+}
+
+#[test]
+fn expiry_takes_precedence_over_suspension() {
+    let credential = credential(CredentialStatus::Suspended, EXPIRES_AT);
+    assert_eq!(credential.effective_status(ISSUED_AT), CredentialStatus::Suspended);
+    assert!(!credential.is_active(ISSUED_AT));
+    assert_eq!(credential.effective_status(EXPIRES_AT + 1), CredentialStatus::Expired);
+}
+
+#[test]
+fn revocation_is_final() {
+    let credential = credential(CredentialStatus::Revoked, EXPIRES_AT);
+    assert_eq!(credential.effective_status(ISSUED_AT), CredentialStatus::Revoked);
+    assert_eq!(credential.effective_status(EXPIRES_AT + 1), CredentialStatus::Revoked);
+    assert!(!credential.is_active(ISSUED_AT));
+}
+
+#[test]
+fn only_issuer_or_revoker_can_revoke() {
+    let credential = credential(CredentialStatus::Active, 0);
+    assert!(credential.can_revoke(&Pubkey::new_from_array([3; 32])));
+    assert!(credential.can_revoke(&Pubkey::new_from_array([5; 32])));
+    assert!(!credential.can_revoke(&Pubkey::new_from_array([4; 32])));
+}
+
+#[test]
+fn fits_allocated_space() {
+    let mut data = Vec::new();
+    credential(CredentialStatus::Active, EXPIRES_AT).try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), Credential::SPACE);
+}
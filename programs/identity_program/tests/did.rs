@@ -0,0 +1,211 @@
+//! Conformance tests for `did:sol` identifiers and DID Core rendering. The
+//! expected documents are the canonical form an off-chain verifier must
+//! produce for the same record: sorted keys, no whitespace.
+
+mod common;
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use common::unhex;
+use identity_program::did::{self, SolDid, SolanaNetwork};
+use identity_program::{
+    DidDocument, DidRecord, ServiceEndpoint, VerificationKeyType, VerificationMethod,
+};
+
+const DID: &str = "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q";
+const SUBJECT_KEY: &str = "7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q";
+// Pubkey::new_from_array([7; 32])
+const SECOND_CONTROLLER: &str = "US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx";
+
+// secp256k1 generator point, uncompressed
+const SECP256K1_G: &str = "\
+0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+const FULL_DOCUMENT: &str = r##"{
+  "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+  "assertionMethod": [
+    "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-1",
+    "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-2"
+  ],
+  "authentication": [
+    "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-1",
+    "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-2"
+  ],
+  "controller": [
+    "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+    "did:sol:devnet:US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx"
+  ],
+  "id": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+  "service": [
+    {
+      "id": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#kyc",
+      "serviceEndpoint": "https://api.copym.xyz/credentials",
+      "type": "CredentialRegistry"
+    }
+  ],
+  "verificationMethod": [
+    {
+      "controller": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+      "id": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-1",
+      "publicKeyMultibase": "z6MkeXCES4onVW4up9Qgz1KRnZsKmGufcaZxF6Zpv2w5QwUK",
+      "type": "Multikey"
+    },
+    {
+      "controller": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+      "id": "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q#key-2",
+      "publicKeyMultibase": "zQ3shVc2UkAfJCdc1TR8E66J85h48P43r93q8jGPkPpjF9Ef9",
+      "type": "Multikey"
+    }
+  ]
+}"##;
+
+const MINIMAL_DOCUMENT: &str = r#"{"@context":["https://www.w3.org/ns/did/v1","https://w3id.org/security/multikey/v1"],"controller":["did:sol:mainnet:US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx"],"id":"did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q"}"#;
+
+const METADATA: &str = r#"{"created":"2024-06-10T06:13:20Z","deactivated":false,"documentCid":"bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi","updated":"2024-06-10T06:15:00Z"}"#;
+
+fn canonical(json: &str) -> String {
+    did::to_canonical_string(&serde_json::from_str(json).unwrap())
+}
+
+fn full_document() -> DidDocument {
+    DidDocument {
+        did: DID.to_string(),
+        controllers: vec![
+            Pubkey::from_str(SUBJECT_KEY).unwrap(),
+            Pubkey::new_from_array([7; 32]),
+        ],
+        verification_methods: vec![
+            VerificationMethod {
+                id: "key-1".to_string(),
+                key_type: VerificationKeyType::Ed25519,
+                public_key: (1..=32).collect(),
+            },
+            VerificationMethod {
+                id: "key-2".to_string(),
+                key_type: VerificationKeyType::Secp256k1,
+                public_key: unhex(SECP256K1_G),
+            },
+        ],
+        services: vec![ServiceEndpoint {
+            id: "kyc".to_string(),
+            service_type: "CredentialRegistry".to_string(),
+            endpoint: "https://api.copym.xyz/credentials".to_string(),
+        }],
+        document_cid: Some("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string()),
+    }
+}
+
+#[test]
+fn sorts_keys_at_every_level() {
+    assert_eq!(
+        canonical(r#"{ "b": [{ "z": 1, "a": null }], "a": { "y": "x", "c": true } }"#),
+        r#"{"a":{"c":true,"y":"x"},"b":[{"a":null,"z":1}]}"#
+    );
+}
+
+#[test]
+fn parses_and_formats_sol_did() {
+    let did = SolDid::from_str(DID).unwrap();
+    assert_eq!(did.network, SolanaNetwork::Devnet);
+    assert_eq!(did.account, Pubkey::from_str(SUBJECT_KEY).unwrap());
+    assert_eq!(did.to_string(), DID);
+
+    for network in ["mainnet", "devnet", "testnet", "localnet"] {
+        let s = format!("did:sol:{network}:{SECOND_CONTROLLER}");
+        assert_eq!(SolDid::from_str(&s).unwrap().to_string(), s);
+    }
+}
+
+#[test]
+fn rejects_non_canonical_sol_did() {
+    for s in [
+        "",
+        "did:sol",
+        "did:sol:devnet",
+        "DID:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:SOL:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:ethr:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:sol:Devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:sol:mainnet-beta:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:sol:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q:extra",
+        "did:sol:devnet:0Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+        // Leading '1's decode to zero bytes, changing the key length
+        "did:sol:devnet:17Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q",
+    ] {
+        assert!(SolDid::from_str(s).is_err(), "{s}");
+    }
+}
+
+#[test]
+fn renders_full_document() {
+    let rendered = did::render_document(&full_document(), SolanaNetwork::Devnet);
+    assert_eq!(did::to_canonical_string(&rendered), canonical(FULL_DOCUMENT));
+}
+
+#[test]
+fn renders_minimal_document() {
+    let document = DidDocument {
+        did: DID.to_string(),
+        controllers: vec![Pubkey::new_from_array([7; 32])],
+        verification_methods: Vec::new(),
+        services: Vec::new(),
+        document_cid: None,
+    };
+    let rendered = did::render_document(&document, SolanaNetwork::Mainnet);
+    assert_eq!(did::to_canonical_string(&rendered), MINIMAL_DOCUMENT);
+}
+
+#[test]
+fn compressed_and_uncompressed_secp256k1_keys_agree() {
+    let uncompressed = unhex(SECP256K1_G);
+    let mut compressed = vec![0x02 | (uncompressed[64] & 1)];
+    compressed.extend_from_slice(&uncompressed[1..33]);
+
+    let key = |public_key| {
+        did::public_key_multibase(&VerificationMethod {
+            id: "key".to_string(),
+            key_type: VerificationKeyType::Secp256k1,
+            public_key,
+        })
+        .unwrap()
+    };
+    assert_eq!(key(uncompressed), key(compressed));
+
+    let invalid = VerificationMethod {
+        id: "key".to_string(),
+        key_type: VerificationKeyType::Ed25519,
+        public_key: vec![0; 33],
+    };
+    assert!(did::public_key_multibase(&invalid).is_err());
+}
+
+#[test]
+fn renders_resolution_metadata() {
+    let record = DidRecord {
+        did_hash: identity_program::did_hash(DID),
+        document: full_document(),
+        created_at: 1_718_000_000,
+        updated_at: 1_718_000_100,
+        deactivated: false,
+    };
+    assert_eq!(did::to_canonical_string(&did::render_metadata(&record)), METADATA);
+
+    // Leap day and century boundaries
+    let record = DidRecord {
+        created_at: 951_868_799,
+        updated_at: 4_102_444_800,
+        deactivated: true,
+        document: DidDocument {
+            document_cid: None,
+            ..record.document
+        },
+        ..record
+    };
+    assert_eq!(
+        did::to_canonical_string(&did::render_metadata(&record)),
+        r#"{"created":"2000-02-29T23:59:59Z","deactivated":true,"updated":"2100-01-01T00:00:00Z"}"#
+    );
+}
@@ -0,0 +1,157 @@
+//! Tests for the limits `DidDocument::validate` enforces before a document is
+//! stored: entry counts, unique controllers, and verification key encodings.
+//! Also covers reading the key a `did:sol` identifier names.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{error, unhex};
+use identity_program::{
+    sol_did_key, DidDocument, ErrorCode, ServiceEndpoint, VerificationKeyType, VerificationMethod,
+};
+
+// secp256k1 generator point, compressed and uncompressed
+const SECP256K1_G_COMPRESSED: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const SECP256K1_G: &str = "\
+0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+fn method(key_type: VerificationKeyType, public_key: Vec<u8>) -> VerificationMethod {
+    VerificationMethod {
+        id: "key-1".to_string(),
+        key_type,
+        public_key,
+    }
+}
+
+fn service() -> ServiceEndpoint {
+    ServiceEndpoint {
+        id: "kyc".to_string(),
+        service_type: "CredentialRegistry".to_string(),
+        endpoint: "https://api.copym.xyz/credentials".to_string(),
+    }
+}
+
+fn document() -> DidDocument {
+    DidDocument {
+        did: "did:sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q".to_string(),
+        controllers: vec![Pubkey::new_from_array([7; 32])],
+        verification_methods: vec![method(VerificationKeyType::Ed25519, vec![9; 32])],
+        services: vec![service()],
+        document_cid: None,
+    }
+}
+
+#[test]
+fn accepts_documents_within_limits() {
+    document().validate().unwrap();
+
+    let mut full = document();
+    full.controllers = (0..DidDocument::MAX_CONTROLLERS as u8)
+        .map(|i| Pubkey::new_from_array([i; 32]))
+        .collect();
+    full.verification_methods = vec![
+        method(VerificationKeyType::Ed25519, vec![9; 32]),
+        method(VerificationKeyType::Secp256k1, unhex(SECP256K1_G_COMPRESSED)),
+        method(VerificationKeyType::Secp256k1, unhex(SECP256K1_G)),
+    ];
+    full.services = vec![service(); DidDocument::MAX_SERVICES];
+    full.document_cid = Some("b".repeat(DidDocument::MAX_CID_LENGTH));
+    full.validate().unwrap();
+}
+
+#[test]
+fn rejects_too_many_entries() {
+    let mut no_controllers = document();
+    no_controllers.controllers.clear();
+    assert_eq!(no_controllers.validate().unwrap_err(), error(ErrorCode::TooManyDidEntries));
+
+    let mut controllers = document();
+    controllers.controllers = (0..=DidDocument::MAX_CONTROLLERS as u8)
+        .map(|i| Pubkey::new_from_array([i; 32]))
+        .collect();
+    assert_eq!(controllers.validate().unwrap_err(), error(ErrorCode::TooManyDidEntries));
+
+    let mut methods = document();
+    methods.verification_methods = vec![
+        method(VerificationKeyType::Ed25519, vec![9; 32]);
+        DidDocument::MAX_VERIFICATION_METHODS + 1
+    ];
+    assert_eq!(methods.validate().unwrap_err(), error(ErrorCode::TooManyDidEntries));
+
+    let mut services = document();
+    services.services = vec![service(); DidDocument::MAX_SERVICES + 1];
+    assert_eq!(services.validate().unwrap_err(), error(ErrorCode::TooManyDidEntries));
+}
+
+#[test]
+fn rejects_duplicate_controllers() {
+    let mut document = document();
+    document.controllers = vec![
+        Pubkey::new_from_array([7; 32]),
+        Pubkey::new_from_array([8; 32]),
+        Pubkey::new_from_array([7; 32]),
+    ];
+    assert_eq!(document.validate().unwrap_err(), error(ErrorCode::InvalidDidController));
+}
+
+#[test]
+fn rejects_malformed_keys() {
+    let malformed = [
+        method(VerificationKeyType::Ed25519, vec![9; 33]),
+        method(VerificationKeyType::Secp256k1, vec![9; 32]),
+        // Compressed and uncompressed keys with the other's prefix
+        method(VerificationKeyType::Secp256k1, {
+            let mut key = unhex(SECP256K1_G_COMPRESSED);
+            key[0] = 0x04;
+            key
+        }),
+        method(VerificationKeyType::Secp256k1, {
+            let mut key = unhex(SECP256K1_G);
+            key[0] = 0x02;
+            key
+        }),
+    ];
+    for method in malformed {
+        let mut document = document();
+        document.verification_methods = vec![method];
+        assert_eq!(document.validate().unwrap_err(), error(ErrorCode::InvalidVerificationMethod));
+    }
+}
+
+#[test]
+fn rejects_overlong_strings() {
+    let mut did = document();
+    did.did = format!("did:sol:{}", "a".repeat(DidDocument::MAX_DID_LENGTH));
+    assert_eq!(did.validate().unwrap_err(), error(ErrorCode::StringTooLong));
+
+    let mut not_a_did = document();
+    not_a_did.did = "sol:devnet:7Yq3mE4fbCZ7bPp1F6oCkqVBGQ7cNwhhXUjDRzcNnK8Q".to_string();
+    assert_eq!(not_a_did.validate().unwrap_err(), error(ErrorCode::InvalidDid));
+
+    let mut endpoint = document();
+    endpoint.services[0].endpoint = "a".repeat(DidDocument::MAX_ENDPOINT_LENGTH + 1);
+    assert_eq!(endpoint.validate().unwrap_err(), error(ErrorCode::StringTooLong));
+
+    let mut cid = document();
+    cid.document_cid = Some("b".repeat(DidDocument::MAX_CID_LENGTH + 1));
+    assert_eq!(cid.validate().unwrap_err(), error(ErrorCode::StringTooLong));
+}
+
+#[test]
+fn reads_sol_did_key() {
+    assert_eq!(
+        sol_did_key("did:sol:devnet:US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx").unwrap(),
+        Some(Pubkey::new_from_array([7; 32]))
+    );
+    assert_eq!(sol_did_key("did:ethr:0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap(), None);
+
+    for malformed in [
+        "did:sol:devnet:not-base58",
+        "did:sol:devnet:",
+        // Extra leading zero bytes
+        "did:sol:devnet:11US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx",
+    ] {
+        assert_eq!(sol_did_key(malformed).unwrap_err(), error(ErrorCode::InvalidDid));
+    }
+}
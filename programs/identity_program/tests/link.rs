@@ -0,0 +1,107 @@
+//! Tests for EVM address link proofs. The signature fixture is `personal_sign`
+//! by Hardhat account #0 (0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266) over the
+//! link message below. Also covers which links inbound role syncs follow.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{error, unhex};
+use identity_program::{eth_message_hash, link_message, linked_account, recover_eth_address, AddressLink, ErrorCode};
+
+const ADDRESS: &str = "f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+const MESSAGE: &str = "Link 0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266 on Wormhole chain 5 to Solana account US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx for program HU18d3qUrvLK52mQ2AoNKEnV6m1B6VreZ8M7eUE5GBew";
+
+const MESSAGE_HASH: &str = "f4c0951f28b8e79aa469c8671582fd98ffd46ee711b62cc264212e40d47d24a5";
+
+const SIGNATURE: &str = "\
+e7f9f7f4004723102a7d7f1e9cdb9372c0104cfb649e97168180dff7861a4e36\
+6b6020100cd9f942bba007dff19dacdeafd24b91cfbcb618d160d7a0b335fa61\
+1c";
+
+// secp256k1 order minus the fixture's s value
+const HIGH_S: &str = "949fdfeff32606bd445ff8200e6253200adc9154df8bea22ee7186ec1d0046e0";
+
+fn address() -> [u8; 20] {
+    unhex(ADDRESS).try_into().unwrap()
+}
+
+fn signature() -> [u8; 65] {
+    unhex(SIGNATURE).try_into().unwrap()
+}
+
+#[test]
+fn builds_link_message() {
+    let message = link_message(5, &address(), &Pubkey::new_from_array([7; 32]));
+    assert_eq!(message, MESSAGE);
+    assert_eq!(eth_message_hash(&message).to_vec(), unhex(MESSAGE_HASH));
+}
+
+#[test]
+fn recovers_signer_address() {
+    let hash = eth_message_hash(MESSAGE);
+    assert_eq!(recover_eth_address(&hash, &signature()).unwrap(), address());
+
+    // Raw recovery ids are accepted as well as 27/28
+    let mut signature = signature();
+    signature[64] -= 27;
+    assert_eq!(recover_eth_address(&hash, &signature).unwrap(), address());
+
+    // Any other message recovers some other address
+    let other = eth_message_hash(&link_message(2, &address(), &Pubkey::new_from_array([7; 32])));
+    assert_ne!(recover_eth_address(&other, &signature).unwrap(), address());
+}
+
+#[test]
+fn rejects_malleable_and_malformed_signatures() {
+    let hash = eth_message_hash(MESSAGE);
+
+    let mut high_s = signature();
+    high_s[32..64].copy_from_slice(&unhex(HIGH_S));
+    high_s[64] = 0x1b;
+    assert_eq!(
+        recover_eth_address(&hash, &high_s).unwrap_err(),
+        error(ErrorCode::InvalidLinkSignature)
+    );
+
+    let mut bad_v = signature();
+    bad_v[64] = 29;
+    assert_eq!(
+        recover_eth_address(&hash, &bad_v).unwrap_err(),
+        error(ErrorCode::InvalidLinkSignature)
+    );
+
+    let mut zero_r = signature();
+    zero_r[..32].fill(0);
+    assert_eq!(
+        recover_eth_address(&hash, &zero_r).unwrap_err(),
+        error(ErrorCode::InvalidLinkSignature)
+    );
+}
+
+#[test]
+fn role_sync_follows_verified_links_only() {
+    let mut link = AddressLink {
+        chain_id: 5,
+        foreign_address: address(),
+        account: Pubkey::new_from_array([7; 32]),
+        linked_at: 1_718_000_000,
+        verified: true,
+    };
+    assert_eq!(linked_account(Some(&link), 5, &address()).unwrap(), link.account);
+    assert_eq!(
+        linked_account(Some(&link), 2, &address()).unwrap_err(),
+        error(ErrorCode::InvalidAddressLink)
+    );
+    assert_eq!(
+        linked_account(None, 5, &address()).unwrap_err(),
+        error(ErrorCode::InvalidAddressLink)
+    );
+
+    // Set by a credential manager without the foreign key's signature
+    link.verified = false;
+    assert_eq!(
+        linked_account(Some(&link), 5, &address()).unwrap_err(),
+        error(ErrorCode::AddressLinkNotVerified)
+    );
+}
@@ -0,0 +1,123 @@
+//! Property tests for the incoming payload decoders: arbitrary input must be
+//! rejected with an error rather than a panic, and well-formed input must
+//! decode back to the values it was built from.
+
+use anchor_lang::prelude::Pubkey;
+use identity_program::abi::{self, AbiEncoder};
+use identity_program::payload::*;
+use identity_program::MessagePayload;
+use proptest::prelude::*;
+
+fn decode_all(data: &[u8]) {
+    let _ = MessagePayload::decode(data);
+    let _ = deserialize_verification(data);
+    let _ = deserialize_asset_creation(data);
+    let _ = deserialize_token_transfer(data);
+    let _ = deserialize_credential_verification(data);
+    let _ = deserialize_role_sync(data);
+    let _ = deserialize_did_resolution(data);
+}
+
+proptest! {
+    #[test]
+    fn random_bytes_never_panic(data in proptest::collection::vec(any::<u8>(), 0..512)) {
+        decode_all(&data);
+    }
+
+    #[test]
+    fn random_words_never_panic(words in proptest::collection::vec(
+        prop_oneof![
+            (0u128..512).prop_map(abi::uint_word),
+            any::<u128>().prop_map(abi::uint_word),
+            any::<[u8; 32]>(),
+        ],
+        0..12,
+    )) {
+        decode_all(&words.concat());
+    }
+
+    #[test]
+    fn mutated_payloads_never_panic(
+        request_id in any::<u64>(),
+        did in proptest::collection::vec(any::<u8>(), 0..160),
+        index in any::<prop::sample::Index>(),
+        byte in any::<u8>(),
+        truncate in any::<bool>(),
+    ) {
+        let mut data = AbiEncoder::new()
+            .push_uint(request_id.into())
+            .push_bytes(&did)
+            .push_uint(1)
+            .finish();
+        let at = index.index(data.len());
+        if truncate {
+            data.truncate(at);
+        } else {
+            data[at] = byte;
+        }
+        decode_all(&data);
+    }
+
+    #[test]
+    fn verification_round_trips(
+        request_id in any::<u64>(),
+        did in proptest::collection::vec(any::<u8>(), 0..=128),
+        sbt_token_id in any::<u64>(),
+        credential_hash in any::<[u8; 32]>(),
+    ) {
+        let data = AbiEncoder::new()
+            .push_uint(request_id.into())
+            .push_bytes(&did)
+            .push_uint(sbt_token_id.into())
+            .push_bytes32(&credential_hash)
+            .finish();
+        prop_assert_eq!(
+            deserialize_verification(&data).unwrap(),
+            (request_id, did, sbt_token_id, credential_hash)
+        );
+
+        let mut trailing = data.clone();
+        trailing.push(0);
+        prop_assert!(deserialize_verification(&trailing).is_err());
+        prop_assert!(deserialize_verification(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn token_transfer_round_trips(
+        transfer_id in any::<u64>(),
+        token_address in any::<[u8; 20]>(),
+        amount in any::<[u8; 32]>(),
+        recipient in any::<[u8; 32]>(),
+    ) {
+        let data = AbiEncoder::new()
+            .push_uint(transfer_id.into())
+            .push_address(&token_address)
+            .push_bytes32(&amount)
+            .push_bytes32(&recipient)
+            .finish();
+        prop_assert_eq!(
+            deserialize_token_transfer(&data).unwrap(),
+            (transfer_id, token_address, amount, Pubkey::new_from_array(recipient))
+        );
+        prop_assert!(deserialize_token_transfer(&data[..96]).is_err());
+    }
+
+    #[test]
+    fn role_sync_round_trips(
+        request_id in any::<u64>(),
+        role in any::<[u8; 32]>(),
+        account in any::<[u8; 20]>(),
+        is_grant in any::<bool>(),
+    ) {
+        let data = AbiEncoder::new()
+            .push_uint(request_id.into())
+            .push_bytes32(&role)
+            .push_address(&account)
+            .push_bool(is_grant)
+            .finish();
+        prop_assert_eq!(
+            deserialize_role_sync(&data).unwrap(),
+            (request_id, role, account, is_grant)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Tests for reading a role's admin role from its `RoleAdmin` account, before
+//! and after `set_role_admin` has created it.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::error;
+use identity_program::{role_admin, roles, ErrorCode, RoleAdmin};
+
+const ROLE: [u8; 32] = [7; 32];
+
+fn role_admin_address(role: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[RoleAdmin::SEED_PREFIX, role], &identity_program::ID).0
+}
+
+fn read(key: &Pubkey, owner: &Pubkey, data: &mut [u8]) -> Result<[u8; 32]> {
+    let mut lamports = 0;
+    let info = AccountInfo::new(key, false, true, &mut lamports, data, owner, false, 0);
+    role_admin(&info, &ROLE)
+}
+
+#[test]
+fn defaults_to_default_admin_role() {
+    let key = role_admin_address(&ROLE);
+    assert_eq!(read(&key, &system_program::ID, &mut []).unwrap(), roles::DEFAULT_ADMIN_ROLE);
+}
+
+#[test]
+fn reads_stored_admin_role() {
+    let key = role_admin_address(&ROLE);
+    let mut data = vec![0u8; RoleAdmin::SPACE];
+    RoleAdmin {
+        role: ROLE,
+        admin_role: roles::BRIDGE_ADMIN_ROLE,
+    }
+    .try_serialize(&mut &mut data[..])
+    .unwrap();
+    assert_eq!(read(&key, &identity_program::ID, &mut data).unwrap(), roles::BRIDGE_ADMIN_ROLE);
+}
+
+#[test]
+fn rejects_other_addresses() {
+    let key = role_admin_address(&[8; 32]);
+    assert_eq!(
+        read(&key, &system_program::ID, &mut []).unwrap_err(),
+        error(ErrorCode::InvalidRoleAccount)
+    );
+}
@@ -57,7 +57,9 @@ async function main() {
 
   console.log("Bridging 50 GOLD tokens...");
   const targetChain = "solana_devnet"; // Placeholder for Solana integration
-  const bridgeTx = await crossChainBridge.bridgeTokens(goldTokenAddress, amountToBridge, targetChain, gasOptions);
+  // Solana wallet that owns the receiving token account
+  const recipient = ethers.toBeHex(ethers.decodeBase58(process.env.SOLANA_RECIPIENT), 32);
+  const bridgeTx = await crossChainBridge.bridgeTokens(goldTokenAddress, amountToBridge, targetChain, recipient, gasOptions);
   const receipt = await bridgeTx.wait();
   console.log("Bridge Tx Hash:", bridgeTx.hash);
   console.log("Deployer GOLD balance after bridge:", ethers.formatUnits(await goldToken.balanceOf(deployer.address), 18));
@@ -32,7 +32,9 @@ async function main() {
   console.log("Allowance for bridge:", ethers.formatUnits(await goldToken.allowance(deployer.address, crossChainBridge.target), 18));
 
   console.log("Bridging 50 GOLD tokens...");
-  const bridgeTx = await crossChainBridge.bridgeTokens(goldToken.target, ethers.parseUnits("50", 18), "solana_devnet");
+  // No Solana side locally, so any nonzero recipient will do
+  const recipient = ethers.zeroPadValue(deployer.address, 32);
+  const bridgeTx = await crossChainBridge.bridgeTokens(goldToken.target, ethers.parseUnits("50", 18), "solana_devnet", recipient);
   const receipt = await bridgeTx.wait();
   console.log("Bridge Tx Hash:", bridgeTx.hash);
   console.log("Deployer GOLD balance after bridge:", ethers.formatUnits(await goldToken.balanceOf(deployer.address), 18));
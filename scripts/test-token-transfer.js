@@ -36,7 +36,7 @@ async function main() {
     "function hasRole(bytes32 role, address account) external view returns (bool)",
     "function commodityTokens(string memory) external view returns (address)",
     "function registerCommodityToken(string memory commodityType, address tokenAddress) external",
-    "function bridgeTokens(address tokenAddress, uint256 amount, string memory targetChain) external",
+    "function bridgeTokens(address tokenAddress, uint256 amount, string memory targetChain, bytes32 recipient) external",
     "function tokenTransferRequests(uint256) external view returns (uint256 transferId, address tokenAddress, uint256 amount, string sourceChain, string targetChain, address sender, bool completed)",
     "event TokenTransferInitiated(uint256 indexed transferId, address token, uint256 amount, string targetChain)"
   ];
@@ -200,7 +200,9 @@ async function main() {
     // 5. Initiate token transfer
     console.log("Initiating token transfer to Solana...");
     try {
-      const transferTx = await bridge.bridgeTokens(goldTokenAddress, amountToTransfer, "solana_devnet");
+      // Solana wallet that owns the receiving token account
+      const recipient = ethers.toBeHex(ethers.decodeBase58(process.env.SOLANA_RECIPIENT), 32);
+      const transferTx = await bridge.bridgeTokens(goldTokenAddress, amountToTransfer, "solana_devnet", recipient);
       
       console.log("Transaction sent, waiting for confirmation...");
       console.log("Transaction hash:", transferTx.hash);
@@ -38,7 +38,8 @@ describe("CrossChainBridge with CommodityToken", function () {
 
     // Approve and bridge tokens
     await commodityToken.approve(crossChainBridge.address, ethers.utils.parseUnits("50", 18));
-    const tx = await crossChainBridge.bridgeTokens(commodityToken.address, ethers.utils.parseUnits("50", 18), "solana_devnet");
+    const recipient = ethers.utils.hexZeroPad(addr1.address, 32);
+    const tx = await crossChainBridge.bridgeTokens(commodityToken.address, ethers.utils.parseUnits("50", 18), "solana_devnet", recipient);
     const receipt = await tx.wait();
     const transferId = receipt.events.find(e => e.event === "TokenTransferInitiated").args.transferId;
 